@@ -0,0 +1,697 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fmt,
+};
+
+use crate::{disasm::ROM_START, instruction::Instruction};
+
+// expanding more macros than this means one expands itself
+const MAX_EXPANSIONS: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub rom: Vec<u8>,
+    // label addresses
+    pub symbols: BTreeMap<String, u16>,
+}
+
+impl Program {
+    // one `0xADDR name` line per label, ordered by address
+    pub fn symbol_map(&self) -> String {
+        let mut symbols: Vec<(&String, &u16)> = self.symbols.iter().collect();
+        symbols.sort_by_key(|(name, addr)| (**addr, *name));
+        symbols
+            .iter()
+            .map(|(name, addr)| format!("0x{:04X} {}\n", addr, name))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+// assembles Octo source into a ROM loaded at 0x200
+pub fn assemble(source: &str) -> Result<Program, AssembleError> {
+    let mut assembler = Assembler::new(source);
+    while !assembler.tokens.is_empty() {
+        assembler.statement()?;
+    }
+    assembler.finish()
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    text: String,
+    line: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    // the low 12 bits of an opcode
+    Address,
+    // the word following F000
+    Long,
+}
+
+struct Fixup {
+    pos: usize,
+    field: Field,
+    label: String,
+    line: usize,
+}
+
+struct Macro {
+    args: Vec<String>,
+    body: Vec<Token>,
+}
+
+enum Block {
+    If {
+        jump: usize,
+        line: usize,
+    },
+    Else {
+        jump: usize,
+        line: usize,
+    },
+    Loop {
+        start: u16,
+        exits: Vec<usize>,
+        line: usize,
+    },
+}
+
+// (skips when the condition holds, skips when it doesn't)
+type Condition = (Instruction, Instruction);
+
+struct Assembler {
+    tokens: VecDeque<Token>,
+    line: usize,
+    rom: Vec<u8>,
+    labels: BTreeMap<String, u16>,
+    consts: HashMap<String, i64>,
+    aliases: HashMap<String, usize>,
+    macros: HashMap<String, Macro>,
+    fixups: Vec<Fixup>,
+    blocks: Vec<Block>,
+    expansions: usize,
+}
+
+impl Assembler {
+    fn new(source: &str) -> Assembler {
+        let tokens = source
+            .lines()
+            .enumerate()
+            .flat_map(|(index, line)| {
+                let code = line.split('#').next().unwrap_or("");
+                code.split_whitespace().map(move |text| Token {
+                    text: text.to_string(),
+                    line: index + 1,
+                })
+            })
+            .collect();
+
+        Assembler {
+            tokens,
+            line: 1,
+            rom: Vec::new(),
+            labels: BTreeMap::new(),
+            consts: HashMap::new(),
+            aliases: HashMap::new(),
+            macros: HashMap::new(),
+            fixups: Vec::new(),
+            blocks: Vec::new(),
+            expansions: 0,
+        }
+    }
+
+    fn finish(mut self) -> Result<Program, AssembleError> {
+        if let Some(block) = self.blocks.last() {
+            let (line, message) = match block {
+                Block::If { line, .. } | Block::Else { line, .. } => (*line, "missing `end`"),
+                Block::Loop { line, .. } => (*line, "missing `again`"),
+            };
+            return Err(AssembleError {
+                line,
+                message: message.to_string(),
+            });
+        }
+
+        for fixup in std::mem::take(&mut self.fixups) {
+            self.line = fixup.line;
+            let Some(&addr) = self.labels.get(&fixup.label) else {
+                return Err(self.error(format!("undefined label `{}`", fixup.label)));
+            };
+            self.patch(fixup.pos, fixup.field, addr)?;
+        }
+
+        Ok(Program {
+            rom: self.rom,
+            symbols: self.labels,
+        })
+    }
+
+    fn error(&self, message: String) -> AssembleError {
+        AssembleError {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn here(&self) -> u16 {
+        ROM_START.wrapping_add(self.rom.len() as u16)
+    }
+
+    fn next(&mut self) -> Result<String, AssembleError> {
+        let token = self
+            .tokens
+            .pop_front()
+            .ok_or_else(|| self.error("unexpected end of input".to_string()))?;
+        self.line = token.line;
+        Ok(token.text)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        if token != expected {
+            return Err(self.error(format!("expected `{}`, found `{}`", expected, token)));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.front().map(|token| token.text.as_str())
+    }
+
+    fn emit_bytes(&mut self, bytes: &[u8]) -> Result<(), AssembleError> {
+        if self.rom.len() + bytes.len() > 0x10000 - ROM_START as usize {
+            return Err(self.error("program doesn't fit in memory".to_string()));
+        }
+        self.rom.extend(bytes);
+        Ok(())
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> Result<(), AssembleError> {
+        self.emit_bytes(&instruction.encode().to_be_bytes())
+    }
+
+    fn patch(&mut self, pos: usize, field: Field, addr: u16) -> Result<(), AssembleError> {
+        match field {
+            Field::Address => {
+                if addr > 0xFFF {
+                    return Err(self.error(format!("address 0x{:X} is out of range", addr)));
+                }
+                self.rom[pos + 1] = addr as u8;
+                self.rom[pos] = self.rom[pos] & 0xF0 | (addr >> 8) as u8;
+            }
+            Field::Long => self.rom[pos + 2..pos + 4].copy_from_slice(&addr.to_be_bytes()),
+        }
+        Ok(())
+    }
+
+    // a jump whose target is filled in once it's known
+    fn emit_placeholder_jump(&mut self) -> Result<usize, AssembleError> {
+        let pos = self.rom.len();
+        self.emit(Instruction::Jump(0))?;
+        Ok(pos)
+    }
+
+    fn statement(&mut self) -> Result<(), AssembleError> {
+        let token = self.next()?;
+        match token.as_str() {
+            ":" => {
+                let name = self.name()?;
+                if self.labels.contains_key(&name) {
+                    return Err(self.error(format!("label `{}` is already defined", name)));
+                }
+                self.labels.insert(name, self.here());
+            }
+            ":alias" => {
+                let name = self.name()?;
+                let register = self.register()?;
+                self.aliases.insert(name, register);
+            }
+            ":const" => {
+                let name = self.name()?;
+                let value = self.value()?;
+                self.consts.insert(name, value);
+            }
+            ":macro" => self.define_macro()?,
+            ":call" => {
+                let addr = self.address(Field::Address)?;
+                self.emit(Instruction::Call(addr))?;
+            }
+            ":byte" => {
+                let byte = self.byte()?;
+                self.emit_bytes(&[byte])?;
+            }
+            "clear" => self.emit(Instruction::Clear)?,
+            "return" | ";" => self.emit(Instruction::Return)?,
+            "scroll-down" => {
+                let n = self.nibble()?;
+                self.emit(Instruction::ScrollDown(n))?;
+            }
+            "scroll-up" => {
+                let n = self.nibble()?;
+                self.emit(Instruction::ScrollUp(n))?;
+            }
+            "scroll-right" => self.emit(Instruction::ScrollRight)?,
+            "scroll-left" => self.emit(Instruction::ScrollLeft)?,
+            "exit" => self.emit(Instruction::Exit)?,
+            "lores" => self.emit(Instruction::Lores)?,
+            "hires" => self.emit(Instruction::Hires)?,
+            "audio" => self.emit(Instruction::Audio)?,
+            "plane" => {
+                let n = self.ranged(0, 3)? as u8;
+                self.emit(Instruction::Plane(n))?;
+            }
+            "jump" => {
+                let addr = self.address(Field::Address)?;
+                self.emit(Instruction::Jump(addr))?;
+            }
+            "jump0" => {
+                let addr = self.address(Field::Address)?;
+                self.emit(Instruction::JumpOffset(addr))?;
+            }
+            "sprite" => {
+                let x = self.register()?;
+                let y = self.register()?;
+                let n = self.nibble()?;
+                self.emit(Instruction::Draw { x, y, n })?;
+            }
+            "save" | "load" => {
+                let x = self.register()?;
+                let instruction = if self.peek() == Some("-") {
+                    self.next()?;
+                    let y = self.register()?;
+                    if token == "save" {
+                        Instruction::SaveRange { x, y }
+                    } else {
+                        Instruction::LoadRange { x, y }
+                    }
+                } else if token == "save" {
+                    Instruction::Save(x)
+                } else {
+                    Instruction::Load(x)
+                };
+                self.emit(instruction)?;
+            }
+            "saveflags" => {
+                let x = self.register()?;
+                self.emit(Instruction::SaveFlags(x))?;
+            }
+            "loadflags" => {
+                let x = self.register()?;
+                self.emit(Instruction::LoadFlags(x))?;
+            }
+            "bcd" => {
+                let x = self.register()?;
+                self.emit(Instruction::Bcd(x))?;
+            }
+            "delay" | "buzzer" | "pitch" => {
+                self.expect(":=")?;
+                let x = self.register()?;
+                self.emit(match token.as_str() {
+                    "delay" => Instruction::SetDelay(x),
+                    "buzzer" => Instruction::SetSound(x),
+                    _ => Instruction::Pitch(x),
+                })?;
+            }
+            "i" => self.index()?,
+            "if" => self.conditional()?,
+            "else" => match self.blocks.pop() {
+                Some(Block::If { jump, line }) => {
+                    let end = self.emit_placeholder_jump()?;
+                    let here = self.here();
+                    self.patch(jump, Field::Address, here)?;
+                    self.blocks.push(Block::Else { jump: end, line });
+                }
+                _ => return Err(self.error("`else` without `if ... begin`".to_string())),
+            },
+            "end" => match self.blocks.pop() {
+                Some(Block::If { jump, .. } | Block::Else { jump, .. }) => {
+                    let here = self.here();
+                    self.patch(jump, Field::Address, here)?;
+                }
+                _ => return Err(self.error("`end` without `if ... begin`".to_string())),
+            },
+            "loop" => {
+                let line = self.line;
+                self.blocks.push(Block::Loop {
+                    start: self.here(),
+                    exits: Vec::new(),
+                    line,
+                });
+            }
+            "while" => {
+                let (skip_if_true, _) = self.condition()?;
+                self.emit(skip_if_true)?;
+                let exit = self.emit_placeholder_jump()?;
+                match self.blocks.iter_mut().rev().find_map(|block| match block {
+                    Block::Loop { exits, .. } => Some(exits),
+                    _ => None,
+                }) {
+                    Some(exits) => exits.push(exit),
+                    None => return Err(self.error("`while` outside of a loop".to_string())),
+                }
+            }
+            "again" => match self.blocks.pop() {
+                Some(Block::Loop { start, exits, .. }) => {
+                    self.emit(Instruction::Jump(start))?;
+                    let here = self.here();
+                    for exit in exits {
+                        self.patch(exit, Field::Address, here)?;
+                    }
+                }
+                _ => return Err(self.error("`again` without `loop`".to_string())),
+            },
+            _ if self.is_register(&token) => self.assignment(&token)?,
+            _ if parse_number(&token).is_some() || self.consts.contains_key(&token) => {
+                // bare numbers are data, this is how sprites are written
+                let byte = self.to_byte(&token)?;
+                self.emit_bytes(&[byte])?;
+            }
+            _ if self.macros.contains_key(&token) => self.expand_macro(&token)?,
+            _ if token.starts_with(':') => {
+                return Err(self.error(format!("unsupported directive `{}`", token)))
+            }
+            _ => {
+                // a bare label name calls it
+                let addr = self.label_address(token, Field::Address)?;
+                self.emit(Instruction::Call(addr))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn index(&mut self) -> Result<(), AssembleError> {
+        let op = self.next()?;
+        match op.as_str() {
+            ":=" => match self.peek() {
+                Some("hex") | Some("bighex") => {
+                    let font = self.next()?;
+                    let x = self.register()?;
+                    self.emit(if font == "hex" {
+                        Instruction::Font(x)
+                    } else {
+                        Instruction::BigFont(x)
+                    })
+                }
+                Some("long") => {
+                    self.next()?;
+                    let addr = self.address(Field::Long)?;
+                    self.emit(Instruction::LoadLongI)?;
+                    self.emit_bytes(&addr.to_be_bytes())
+                }
+                _ => {
+                    let addr = self.address(Field::Address)?;
+                    self.emit(Instruction::LoadI(addr))
+                }
+            },
+            "+=" => {
+                let x = self.register()?;
+                self.emit(Instruction::AddI(x))
+            }
+            _ => Err(self.error(format!("unknown operator `i {}`", op))),
+        }
+    }
+
+    fn assignment(&mut self, register: &str) -> Result<(), AssembleError> {
+        let x = self.to_register(register)?;
+        let op = self.next()?;
+        let operand = self.next()?;
+
+        if self.is_register(&operand) {
+            let y = self.to_register(&operand)?;
+            let instruction = match op.as_str() {
+                ":=" => Instruction::Move { x, y },
+                "|=" => Instruction::Or { x, y },
+                "&=" => Instruction::And { x, y },
+                "^=" => Instruction::Xor { x, y },
+                "+=" => Instruction::Add { x, y },
+                "-=" => Instruction::Sub { x, y },
+                ">>=" => Instruction::ShiftRight { x, y },
+                "=-" => Instruction::SubReverse { x, y },
+                "<<=" => Instruction::ShiftLeft { x, y },
+                _ => return Err(self.error(format!("unknown operator `{}`", op))),
+            };
+            return self.emit(instruction);
+        }
+
+        let instruction = match (op.as_str(), operand.as_str()) {
+            (":=", "random") => {
+                let kk = self.byte()?;
+                Instruction::Random { x, kk }
+            }
+            (":=", "key") => Instruction::WaitKey(x),
+            (":=", "delay") => Instruction::GetDelay(x),
+            (":=", _) => Instruction::LoadImm {
+                x,
+                kk: self.to_byte(&operand)?,
+            },
+            ("+=", _) => Instruction::AddImm {
+                x,
+                kk: self.to_byte(&operand)?,
+            },
+            ("-=", _) => Instruction::AddImm {
+                x,
+                kk: self.to_byte(&operand)?.wrapping_neg(),
+            },
+            _ => {
+                return Err(self.error(format!(
+                    "operator `{}` needs a register, found `{}`",
+                    op, operand
+                )))
+            }
+        };
+        self.emit(instruction)
+    }
+
+    fn conditional(&mut self) -> Result<(), AssembleError> {
+        let (skip_if_true, skip_if_false) = self.condition()?;
+        let line = self.line;
+        match self.next()?.as_str() {
+            "then" => self.emit(skip_if_false),
+            "begin" => {
+                self.emit(skip_if_true)?;
+                let jump = self.emit_placeholder_jump()?;
+                self.blocks.push(Block::If { jump, line });
+                Ok(())
+            }
+            other => Err(self.error(format!("expected `then` or `begin`, found `{}`", other))),
+        }
+    }
+
+    fn condition(&mut self) -> Result<Condition, AssembleError> {
+        let x = self.register()?;
+        let op = self.next()?;
+        match op.as_str() {
+            "key" => return Ok((Instruction::SkipKey(x), Instruction::SkipNotKey(x))),
+            "-key" => return Ok((Instruction::SkipNotKey(x), Instruction::SkipKey(x))),
+            "==" | "!=" => {}
+            _ => return Err(self.error(format!("unsupported comparison `{}`", op))),
+        }
+
+        let operand = self.next()?;
+        let (equal, not_equal) = if self.is_register(&operand) {
+            let y = self.to_register(&operand)?;
+            (Instruction::SkipEq { x, y }, Instruction::SkipNe { x, y })
+        } else {
+            let kk = self.to_byte(&operand)?;
+            (
+                Instruction::SkipEqImm { x, kk },
+                Instruction::SkipNeImm { x, kk },
+            )
+        };
+        Ok(if op == "==" {
+            (equal, not_equal)
+        } else {
+            (not_equal, equal)
+        })
+    }
+
+    fn define_macro(&mut self) -> Result<(), AssembleError> {
+        let name = self.name()?;
+        let mut args = Vec::new();
+        loop {
+            let token = self.next()?;
+            if token == "{" {
+                break;
+            }
+            args.push(token);
+        }
+
+        let mut body = Vec::new();
+        let mut depth = 1;
+        loop {
+            let token = self
+                .tokens
+                .pop_front()
+                .ok_or_else(|| self.error(format!("macro `{}` is missing `}}`", name)))?;
+            match token.text.as_str() {
+                "{" => depth += 1,
+                "}" => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                break;
+            }
+            body.push(token);
+        }
+        self.macros.insert(name, Macro { args, body });
+        Ok(())
+    }
+
+    fn expand_macro(&mut self, name: &str) -> Result<(), AssembleError> {
+        self.expansions += 1;
+        if self.expansions > MAX_EXPANSIONS {
+            return Err(self.error(format!("macro `{}` expands forever", name)));
+        }
+
+        let arg_count = self.macros[name].args.len();
+        let mut values = HashMap::new();
+        for index in 0..arg_count {
+            let value = self.next()?;
+            values.insert(self.macros[name].args[index].clone(), value);
+        }
+
+        // the expansion reports errors on the line it was used on
+        let line = self.line;
+        for token in self.macros[name].body.iter().rev() {
+            let text = values.get(&token.text).unwrap_or(&token.text).clone();
+            self.tokens.push_front(Token { text, line });
+        }
+        Ok(())
+    }
+
+    fn name(&mut self) -> Result<String, AssembleError> {
+        let name = self.next()?;
+        if parse_number(&name).is_some() || parse_register(&name).is_some() {
+            return Err(self.error(format!("`{}` can't be used as a name", name)));
+        }
+        Ok(name)
+    }
+
+    fn is_register(&self, token: &str) -> bool {
+        parse_register(token).is_some() || self.aliases.contains_key(token)
+    }
+
+    fn to_register(&self, token: &str) -> Result<usize, AssembleError> {
+        parse_register(token)
+            .or_else(|| self.aliases.get(token).copied())
+            .ok_or_else(|| self.error(format!("expected a register, found `{}`", token)))
+    }
+
+    fn register(&mut self) -> Result<usize, AssembleError> {
+        let token = self.next()?;
+        self.to_register(&token)
+    }
+
+    fn to_value(&self, token: &str) -> Result<i64, AssembleError> {
+        parse_number(token)
+            .or_else(|| self.consts.get(token).copied())
+            .ok_or_else(|| self.error(format!("expected a number, found `{}`", token)))
+    }
+
+    fn value(&mut self) -> Result<i64, AssembleError> {
+        let token = self.next()?;
+        self.to_value(&token)
+    }
+
+    fn ranged(&mut self, min: i64, max: i64) -> Result<i64, AssembleError> {
+        let value = self.value()?;
+        if !(min..=max).contains(&value) {
+            return Err(self.error(format!("{} is out of range {}..={}", value, min, max)));
+        }
+        Ok(value)
+    }
+
+    fn nibble(&mut self) -> Result<u8, AssembleError> {
+        Ok(self.ranged(0, 15)? as u8)
+    }
+
+    // bytes may be written signed, -1 is 0xFF
+    fn to_byte(&self, token: &str) -> Result<u8, AssembleError> {
+        let value = self.to_value(token)?;
+        if !(-128..=255).contains(&value) {
+            return Err(self.error(format!("{} doesn't fit in a byte", value)));
+        }
+        Ok(value as u8)
+    }
+
+    fn byte(&mut self) -> Result<u8, AssembleError> {
+        let token = self.next()?;
+        self.to_byte(&token)
+    }
+
+    // a number, a constant or a label, which may be defined later
+    fn address(&mut self, field: Field) -> Result<u16, AssembleError> {
+        let token = self.next()?;
+        if parse_number(&token).is_some() || self.consts.contains_key(&token) {
+            let max = if field == Field::Long { 0xFFFF } else { 0xFFF };
+            let value = self.to_value(&token)?;
+            if !(0..=max).contains(&value) {
+                return Err(self.error(format!("address {} is out of range", value)));
+            }
+            return Ok(value as u16);
+        }
+        self.label_address(token, field)
+    }
+
+    // addresses of labels that aren't defined yet are patched in by `finish`
+    fn label_address(&mut self, label: String, field: Field) -> Result<u16, AssembleError> {
+        if let Some(&addr) = self.labels.get(&label) {
+            if field == Field::Address && addr > 0xFFF {
+                return Err(self.error(format!("label `{}` is out of range", label)));
+            }
+            return Ok(addr);
+        }
+        self.fixups.push(Fixup {
+            pos: self.rom.len(),
+            field,
+            label,
+            line: self.line,
+        });
+        Ok(0)
+    }
+}
+
+fn parse_register(token: &str) -> Option<usize> {
+    let digit = token.strip_prefix(['v', 'V'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    usize::from_str_radix(digit, 16).ok()
+}
+
+fn parse_number(token: &str) -> Option<i64> {
+    let (negative, digits) = match token.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, token),
+    };
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16).ok()?
+    } else if let Some(binary) = digits.strip_prefix("0b") {
+        i64::from_str_radix(binary, 2).ok()?
+    } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        digits.parse().ok()?
+    } else {
+        return None;
+    };
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+#[path = "./assembler_tests.rs"]
+mod assembler_tests;
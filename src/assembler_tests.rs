@@ -0,0 +1,157 @@
+use super::*;
+
+fn rom(source: &str) -> Vec<u8> {
+    assemble(source).unwrap().rom
+}
+
+fn error(source: &str) -> AssembleError {
+    assemble(source).unwrap_err()
+}
+
+#[test]
+fn test_instructions() {
+    assert_eq!(
+        rom("
+            clear
+            v0 := 0x12   v1 += 3   v2 -= 1
+            v3 := v4   v5 ^= v6   v7 >>= v8   v9 =- va
+            i := 0x300   i += vb   i := hex vc
+            sprite v0 v1 5
+            vd := random 0b1111   ve := key   vf := delay
+            delay := v0   buzzer := v1
+            bcd v2   save v3   load v4 - v5
+            return
+        "),
+        [
+            0x00, 0xE0, 0x60, 0x12, 0x71, 0x03, 0x72, 0xFF, 0x83, 0x40, 0x85, 0x63, 0x87, 0x86,
+            0x89, 0xA7, 0xA3, 0x00, 0xFB, 0x1E, 0xFC, 0x29, 0xD0, 0x15, 0xCD, 0x0F, 0xFE, 0x0A,
+            0xFF, 0x07, 0xF0, 0x15, 0xF1, 0x18, 0xF2, 0x33, 0xF3, 0x55, 0x54, 0x53, 0x00, 0xEE,
+        ]
+    );
+}
+
+#[test]
+fn test_labels() {
+    let program = assemble(
+        "
+        : main
+            jump skip
+            clear
+        : skip
+            update
+            i := long data
+            loop again
+        : update
+            ;
+        : data
+            0b11110000 -1
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(
+        program.rom,
+        [
+            0x12, 0x04, 0x00, 0xE0, 0x22, 0x0C, 0xF0, 0x00, 0x02, 0x0E, 0x12, 0x0A, 0x00, 0xEE,
+            0xF0, 0xFF,
+        ]
+    );
+    assert_eq!(program.symbols["skip"], 0x204);
+    assert_eq!(program.symbols["data"], 0x20E);
+    assert_eq!(
+        program.symbol_map(),
+        "0x0200 main\n0x0204 skip\n0x020C update\n0x020E data\n"
+    );
+}
+
+#[test]
+fn test_alias_and_const() {
+    assert_eq!(
+        rom("
+            :alias x v3
+            :const SPEED 4
+            x := SPEED
+            x += x
+            :byte SPEED
+        "),
+        [0x63, 0x04, 0x83, 0x34, 0x04]
+    );
+}
+
+#[test]
+fn test_if_then() {
+    assert_eq!(
+        rom("
+            if v1 == 2 then v0 := 1
+            if v1 != v2 then v0 := 1
+            if v3 key then v0 := 1
+            if v3 -key then v0 := 1
+        "),
+        [
+            0x41, 0x02, 0x60, 0x01, 0x51, 0x20, 0x60, 0x01, 0xE3, 0xA1, 0x60, 0x01, 0xE3, 0x9E,
+            0x60, 0x01,
+        ]
+    );
+}
+
+#[test]
+fn test_if_else() {
+    assert_eq!(
+        rom("
+            if v0 == 0 begin
+                v1 := 1
+            else
+                v1 := 2
+            end
+        "),
+        [0x30, 0x00, 0x12, 0x08, 0x61, 0x01, 0x12, 0x0A, 0x61, 0x02]
+    );
+}
+
+#[test]
+fn test_loop_while() {
+    assert_eq!(
+        rom("
+            loop
+                v0 += 1
+                while v0 != 10
+            again
+        "),
+        [0x70, 0x01, 0x40, 0x0A, 0x12, 0x08, 0x12, 0x00]
+    );
+}
+
+#[test]
+fn test_macro() {
+    assert_eq!(
+        rom("
+            :macro swap a b { vf := a a := b b := vf }
+            swap v1 v2
+        "),
+        [0x8F, 0x10, 0x81, 0x20, 0x82, 0xF0]
+    );
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        error("clear\njump nowhere"),
+        AssembleError {
+            line: 2,
+            message: "undefined label `nowhere`".to_string()
+        }
+    );
+    assert_eq!(error("\nloop\nv0 += 1").line, 2);
+    assert_eq!(error("v0 := 256").message, "256 doesn't fit in a byte");
+    assert_eq!(error(": a\n: a").message, "label `a` is already defined");
+    assert_eq!(error("else").message, "`else` without `if ... begin`");
+    assert_eq!(
+        error(":macro m { m }\nm").message,
+        "macro `m` expands forever"
+    );
+    assert_eq!(
+        error("v0 |= 1").message,
+        "operator `|=` needs a register, found `1`"
+    );
+    assert_eq!(error(":org 0x300").message, "unsupported directive `:org`");
+}
@@ -0,0 +1,143 @@
+use std::{f32::consts::TAU, fmt, str::FromStr};
+
+// how long the beep takes to fade in or out, short enough to sound instant but avoids clicks
+const RAMP_SECONDS: f32 = 0.005;
+
+// bits in an XO-CHIP audio pattern
+const PATTERN_BITS: f32 = 128.0;
+
+// XO-CHIP plays its pattern at 4000 bits a second at pitch 64, an octave
+// every 48 steps either side of that
+pub fn pattern_rate(pitch: u8) -> f32 {
+    4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0)
+}
+
+// an XO-CHIP sound: the 16 byte bit pattern loaded by F002 and the FX3A pitch
+pub type Pattern = ([u8; 16], u8);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Square,
+    Sine,
+    Triangle,
+}
+
+impl Waveform {
+    // one period spans phase 0.0..1.0, output is in -1.0..=1.0
+    fn sample(self, phase: f32) -> f32 {
+        match self {
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Sine => (phase * TAU).sin(),
+            Waveform::Triangle => 4.0 * (phase - 0.5).abs() - 1.0,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Waveform::Square => "square",
+            Waveform::Sine => "sine",
+            Waveform::Triangle => "triangle",
+        }
+    }
+}
+
+impl fmt::Display for Waveform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Waveform {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Waveform, String> {
+        match s {
+            "square" => Ok(Waveform::Square),
+            "sine" => Ok(Waveform::Sine),
+            "triangle" => Ok(Waveform::Triangle),
+            _ => Err(format!(
+                "unknown waveform `{}` (expected square, sine or triangle)",
+                s
+            )),
+        }
+    }
+}
+
+// tone generator for the sound timer, frontends pull mono f32 samples from it
+pub struct Beeper {
+    pub frequency: f32,
+    pub volume: f32,
+    pub waveform: Waveform,
+    // played instead of the waveform when set
+    pattern: Option<Pattern>,
+    sample_rate: f32,
+    phase: f32,
+    gain: f32,
+    active: bool,
+}
+
+impl Beeper {
+    pub fn new(sample_rate: u32, frequency: f32, volume: f32, waveform: Waveform) -> Beeper {
+        Beeper {
+            frequency,
+            volume: volume.clamp(0.0, 1.0),
+            waveform,
+            pattern: None,
+            sample_rate: sample_rate as f32,
+            phase: 0.0,
+            gain: 0.0,
+            active: false,
+        }
+    }
+
+    pub fn set_active(&mut self, active: bool) {
+        self.active = active;
+    }
+
+    pub fn set_pattern(&mut self, pattern: Option<Pattern>) {
+        self.pattern = pattern;
+    }
+
+    pub fn fill(&mut self, out: &mut [f32]) {
+        let target = if self.active { self.volume } else { 0.0 };
+        let ramp_step = self.volume / (RAMP_SECONDS * self.sample_rate);
+        for sample in out.iter_mut() {
+            if self.gain < target {
+                self.gain = (self.gain + ramp_step).min(target);
+            } else if self.gain > target {
+                self.gain = (self.gain - ramp_step).max(target);
+            }
+
+            // the phase runs over one period of the waveform, or the whole pattern
+            let (level, frequency) = match &self.pattern {
+                Some((bits, pitch)) => {
+                    let bit = (self.phase * PATTERN_BITS) as usize;
+                    let level = if bits[bit / 8] & (0x80 >> (bit % 8)) != 0 {
+                        1.0
+                    } else {
+                        -1.0
+                    };
+                    (level, pattern_rate(*pitch) / PATTERN_BITS)
+                }
+                None => (self.waveform.sample(self.phase), self.frequency),
+            };
+            *sample = level * self.gain;
+            self.phase = (self.phase + frequency / self.sample_rate) % 1.0;
+        }
+
+        // restart the waveform from the top once fully silent, so every beep sounds the same
+        if self.gain == 0.0 {
+            self.phase = 0.0;
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./audio_tests.rs"]
+mod audio_tests;
@@ -0,0 +1,72 @@
+use super::*;
+
+const SAMPLE_RATE: u32 = 48000;
+const RAMP_SAMPLES: usize = 240;
+
+#[test]
+fn test_silent_when_inactive() {
+    let mut beeper = Beeper::new(SAMPLE_RATE, 440.0, 0.5, Waveform::Square);
+    let mut out = [1.0; 64];
+    beeper.fill(&mut out);
+
+    assert!(out.iter().all(|&sample| sample == 0.0));
+}
+
+#[test]
+fn test_ramps_in_and_out() {
+    let mut beeper = Beeper::new(SAMPLE_RATE, 440.0, 0.5, Waveform::Square);
+    beeper.set_active(true);
+    let mut out = [0.0; RAMP_SAMPLES * 2];
+    beeper.fill(&mut out);
+
+    // the first sample is quiet, full volume is only reached after the ramp
+    assert!(out[0].abs() < 0.01);
+    assert!(out[RAMP_SAMPLES / 2].abs() < 0.5);
+    assert!((out[RAMP_SAMPLES + 1].abs() - 0.5).abs() < 1e-6);
+
+    beeper.set_active(false);
+    beeper.fill(&mut out);
+
+    assert!(out[0].abs() > 0.4);
+    assert!(out[RAMP_SAMPLES..].iter().all(|&sample| sample == 0.0));
+}
+
+#[test]
+fn test_pattern() {
+    let mut beeper = Beeper::new(SAMPLE_RATE, 440.0, 0.5, Waveform::Sine);
+    // the first 8 bits high, the rest low
+    let mut bits = [0; 16];
+    bits[0] = 0xFF;
+    beeper.set_pattern(Some((bits, 64)));
+    beeper.set_active(true);
+    // 4000 bits a second is 12 samples a bit, so 1536 for the whole pattern
+    let mut out = [0.0; 1536 + 96];
+    beeper.fill(&mut out);
+
+    assert!(out[..96].iter().all(|&sample| sample > 0.0));
+    assert!(out[96..1536].iter().all(|&sample| sample < 0.0));
+    assert!(out[1536..].iter().all(|&sample| sample > 0.0));
+    assert_eq!(out[1000], -0.5);
+}
+
+#[test]
+fn test_pattern_rate() {
+    assert_eq!(pattern_rate(64), 4000.0);
+    assert_eq!(pattern_rate(112), 8000.0);
+    assert_eq!(pattern_rate(16), 2000.0);
+}
+
+#[test]
+fn test_waveform_shapes() {
+    assert_eq!(Waveform::Square.sample(0.25), 1.0);
+    assert_eq!(Waveform::Square.sample(0.75), -1.0);
+    assert!((Waveform::Sine.sample(0.25) - 1.0).abs() < 1e-6);
+    assert_eq!(Waveform::Triangle.sample(0.0), 1.0);
+    assert_eq!(Waveform::Triangle.sample(0.5), -1.0);
+}
+
+#[test]
+fn test_waveform_from_str() {
+    assert_eq!("sine".parse(), Ok(Waveform::Sine));
+    assert!("sawtooth".parse::<Waveform>().is_err());
+}
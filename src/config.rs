@@ -0,0 +1,108 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use serde::Deserialize;
+
+use crate::palette::Palette;
+
+// a TOML config file. frontends layer a global one with one next to the ROM,
+// later files win:
+//   # drop the default layout and earlier configs' bindings
+//   replace-keys = true
+//
+//   [keys]
+//   Up = 0x5
+//   "Keypad 5" = [0x4, 0x6]
+//
+//   [scancodes]
+//   Q = 0x4
+//
+//   [gamepad]
+//   dpup = 0x5
+//   "leftx-" = 0x7
+//
+// top-level settings go before the first table:
+//   palette = "amber"
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub replace_keys: bool,
+    // SDL keycode names, for keys by the symbol printed on them
+    pub keys: BTreeMap<String, KeyList>,
+    // SDL scancode names, for keys by their position on a US layout
+    pub scancodes: BTreeMap<String, KeyList>,
+    // same as replace-keys, for the gamepad bindings
+    pub replace_gamepad: bool,
+    // SDL gamepad button names, or an axis name followed by the direction
+    // it's pushed in, e.g. `lefty-` for the left stick pushed up
+    pub gamepad: BTreeMap<String, KeyList>,
+    // how far a stick has to be pushed before it counts, from 0.0 to 1.0
+    pub gamepad_deadzone: Option<f32>,
+    // a palette name or hex colors, the same as --palette
+    pub palette: Option<String>,
+}
+
+// the hex keys a host key presses, an empty list unbinds it
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(u8),
+    Many(Vec<u8>),
+}
+
+impl KeyList {
+    pub fn keys(&self) -> &[u8] {
+        match self {
+            KeyList::One(key) => std::slice::from_ref(key),
+            KeyList::Many(keys) => keys,
+        }
+    }
+}
+
+impl FromStr for Config {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Config, String> {
+        let config: Config = toml::from_str(s).map_err(|err| err.message().to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+impl Config {
+    // what serde can't check, shared with the ROM database's entries
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        let tables = [
+            ("keys", &self.keys),
+            ("scancodes", &self.scancodes),
+            ("gamepad", &self.gamepad),
+        ];
+        for (table, bindings) in tables {
+            for (name, keys) in bindings {
+                if let Some(key) = keys.keys().iter().find(|&&key| key > 0xF) {
+                    return Err(format!(
+                        "{}.{}: {:#x} is not a keypad key",
+                        table, name, key
+                    ));
+                }
+            }
+        }
+        if let Some(deadzone) = self.gamepad_deadzone {
+            if !(0.0..1.0).contains(&deadzone) {
+                return Err(format!(
+                    "gamepad-deadzone: {} is not between 0.0 and 1.0",
+                    deadzone
+                ));
+            }
+        }
+        if let Some(palette) = &self.palette {
+            palette
+                .parse::<Palette>()
+                .map_err(|err| format!("palette: {}", err))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "./config_tests.rs"]
+mod config_tests;
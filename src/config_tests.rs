@@ -0,0 +1,72 @@
+use super::*;
+
+#[test]
+fn test_parse() {
+    let config: Config = "
+        replace-keys = true
+        gamepad-deadzone = 0.2
+
+        [keys]
+        Up = 0x5
+        \"Keypad 5\" = [4, 6]
+        Q = []
+
+        [scancodes]
+        W = 0xA
+
+        [gamepad]
+        dpup = 0x5
+        \"lefty-\" = 0x5
+    "
+    .parse()
+    .unwrap();
+
+    assert!(config.replace_keys);
+    assert_eq!(config.keys["Up"].keys(), [5]);
+    assert_eq!(config.keys["Keypad 5"].keys(), [4, 6]);
+    assert_eq!(config.keys["Q"].keys(), [] as [u8; 0]);
+    assert_eq!(config.scancodes["W"].keys(), [0xA]);
+    assert_eq!(config.gamepad["dpup"].keys(), [0x5]);
+    assert_eq!(config.gamepad["lefty-"].keys(), [0x5]);
+    assert_eq!(config.gamepad_deadzone, Some(0.2));
+}
+
+#[test]
+fn test_empty() {
+    assert_eq!("".parse::<Config>(), Ok(Config::default()));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(
+        "[keys]\nUp = 16".parse::<Config>(),
+        Err("keys.Up: 0x10 is not a keypad key".to_string())
+    );
+    assert_eq!(
+        "[scancodes]\nW = [1, 20]".parse::<Config>(),
+        Err("scancodes.W: 0x14 is not a keypad key".to_string())
+    );
+    assert!("[keyz]\nUp = 1"
+        .parse::<Config>()
+        .unwrap_err()
+        .contains("unknown field `keyz`"));
+    assert!("[keys]\nUp = \"five\"".parse::<Config>().is_err());
+    assert_eq!(
+        "[gamepad]\ndpup = 16".parse::<Config>(),
+        Err("gamepad.dpup: 0x10 is not a keypad key".to_string())
+    );
+    assert_eq!(
+        "gamepad-deadzone = 1.5".parse::<Config>(),
+        Err("gamepad-deadzone: 1.5 is not between 0.0 and 1.0".to_string())
+    );
+}
+
+#[test]
+fn test_palette() {
+    let config: Config = "palette = \"#000000, #33ff66\"".parse().unwrap();
+    assert_eq!(config.palette.as_deref(), Some("#000000, #33ff66"));
+    assert_eq!(
+        "palette = \"#000000\"".parse::<Config>(),
+        Err("palette: expected 2 to 4 colors, got 1".to_string())
+    );
+}
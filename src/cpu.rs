@@ -1,8 +1,12 @@
+use std::fmt;
+
 use crate::{
-    display::{Display, HEIGHT, WIDTH},
+    display::{Display, PLANE_MASK},
+    instruction::Instruction,
     keypad::Keypad,
+    quirks::Quirks,
+    rng::{RandomSource, RngMode},
 };
-use rand::Rng;
 
 pub static FONT_SET: [u8; 80] = [
     0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
@@ -23,13 +27,105 @@ pub static FONT_SET: [u8; 80] = [
     0xF0, 0x80, 0xF0, 0x80, 0x80, // F
 ];
 
+// SUPER-CHIP 8x10 font, stored right after the regular font
+pub const HIRES_FONT_ADDR: usize = FONT_SET.len();
+
+pub static HIRES_FONT_SET: [u8; 160] = [
+    0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, // 0
+    0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, // 1
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // 2
+    0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 3
+    0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 5
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 6
+    0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, // 7
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, // 8
+    0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, // 9
+    0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, // A
+    0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, // B
+    0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, // C
+    0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, // D
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, // E
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, // F
+];
+
+// COSMAC VIP machine cycles on top of Instruction::vip_cycles: a skip that's
+// taken, each sprite row DXYN draws (twice that when VX isn't byte aligned and
+// every row straddles two bytes), and each count of FX33's digit loop
+const VIP_SKIP_CYCLES: u64 = 4;
+const VIP_SPRITE_ROW_CYCLES: u64 = 34;
+const VIP_BCD_DIGIT_CYCLES: u64 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Chip8,
+    SuperChip,
+    XoChip,
+}
+
+impl Platform {
+    pub fn memory_size(self) -> usize {
+        match self {
+            Platform::Chip8 | Platform::SuperChip => 0x1000,
+            Platform::XoChip => 0x10000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CpuError {
+    // 2NNN with all 16 stack slots in use
+    StackOverflow { pc: u16 },
+    // 00EE with an empty stack
+    StackUnderflow { pc: u16 },
+    // an access past the end of memory
+    MemoryOutOfBounds { addr: usize, pc: u16 },
+    UnknownOpcode { opcode: u16, pc: u16 },
+}
+
+impl fmt::Display for CpuError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CpuError::StackOverflow { pc } => write!(f, "stack overflow at 0x{:04X}", pc),
+            CpuError::StackUnderflow { pc } => write!(f, "stack underflow at 0x{:04X}", pc),
+            CpuError::MemoryOutOfBounds { addr, pc } => write!(
+                f,
+                "memory access out of bounds (0x{:04X}) at 0x{:04X}",
+                addr, pc
+            ),
+            CpuError::UnknownOpcode { opcode, pc } => {
+                write!(f, "unknown opcode 0x{:04X} at 0x{:04X}", opcode, pc)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CpuError {}
+
+// how far an FX0A has got, the cpu is held until a key goes down and back up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyWait {
+    // keys that were already held when FX0A ran only count once they've been let go
+    Press { register: usize, held: [bool; 16] },
+    // `key` went down, it's loaded into the register when it comes back up
+    Release { register: usize, key: u8 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
 pub struct Cpu {
+    pub platform: Platform,
+    pub quirks: Quirks,
     // index register
     pub i: u16,
     // program counter
     pub pc: u16,
-    // memory
-    pub memory: [u8; 4096],
+    // memory, sized by the platform
+    pub memory: Vec<u8>,
     // registers
     pub v: [u8; 16],
     // peripherals
@@ -43,18 +139,40 @@ pub struct Cpu {
     pub dt: u8,
     // sound timer
     pub st: u8,
+    // SUPER-CHIP persistent flag registers, XO-CHIP extends these to 16
+    pub rpl: [u8; 16],
+    // XO-CHIP audio pattern buffer and playback pitch
+    pub audio_pattern: [u8; 16],
+    pub pitch: u8,
+    // set by 00FD, the program has asked the interpreter to quit
+    pub exited: bool,
+    // a DXYN under the display-wait quirk is holding the cpu until the frame ends
+    pub vblank_wait: bool,
+    // an FX0A waiting for a key press and release
+    pub key_wait: Option<KeyWait>,
+    // source for CXKK, kept across resets
+    pub rng: Box<dyn RandomSource>,
+    // when set, data reads and writes made by instructions are appended here
+    pub memory_trace: Option<Vec<(usize, Access)>>,
+    // running totals for pacing, not part of the machine state: instructions
+    // executed and the COSMAC VIP machine cycles they would have taken
+    pub instructions: u64,
+    pub vip_cycles: u64,
 }
 
-fn read_word(memory: [u8; 4096], index: u16) -> u16 {
-    (memory[index as usize] as u16) << 8 | (memory[(index + 1) as usize] as u16)
+// registers VX..=VY, in descending order when X > Y
+fn register_range(x: usize, y: usize) -> impl Iterator<Item = usize> {
+    (0..=x.abs_diff(y)).map(move |offset| if x <= y { x + offset } else { x - offset })
 }
 
 impl Cpu {
-    pub fn new() -> Cpu {
+    pub fn new(quirks: Quirks) -> Cpu {
         Cpu {
+            platform: Platform::Chip8,
+            quirks,
             i: 0,
             pc: 0,
-            memory: [0; 4096],
+            memory: vec![0; Platform::Chip8.memory_size()],
             v: [0; 16],
             stack: [0; 16],
             display: Display::new(),
@@ -62,29 +180,52 @@ impl Cpu {
             sp: 0,
             st: 0,
             dt: 0,
+            rpl: [0; 16],
+            audio_pattern: [0; 16],
+            pitch: 64,
+            exited: false,
+            vblank_wait: false,
+            key_wait: None,
+            rng: RngMode::Seeded.create(rand::random()),
+            memory_trace: None,
+            instructions: 0,
+            vip_cycles: 0,
         }
     }
 
+    pub fn set_platform(&mut self, platform: Platform) {
+        self.platform = platform;
+        self.memory.resize(platform.memory_size(), 0);
+    }
+
     pub fn reset(&mut self) {
         self.i = 0;
         self.pc = 0x200;
-        self.memory = [0; 4096];
+        self.memory.fill(0);
         self.v = [0; 16];
         self.stack = [0; 16];
-        self.display.cls();
+        self.display.set_hires(false);
+        self.display.planes = 1;
         for i in 0..16 {
             self.keypad.key_up(i as usize);
         }
         self.sp = 0;
         self.dt = 0;
         self.st = 0;
+        self.audio_pattern = [0; 16];
+        self.pitch = 64;
+        self.exited = false;
+        self.vblank_wait = false;
+        self.key_wait = None;
         self.memory[0..FONT_SET.len()].copy_from_slice(&FONT_SET);
+        self.memory[HIRES_FONT_ADDR..HIRES_FONT_ADDR + HIRES_FONT_SET.len()]
+            .copy_from_slice(&HIRES_FONT_SET);
     }
 
     pub fn load(&mut self, data: &[u8]) {
         for (i, &byte) in data.iter().enumerate() {
             let addr = 0x200 + i;
-            if addr < 4096 {
+            if addr < self.memory.len() {
                 self.memory[0x200 + i] = byte;
             } else {
                 break;
@@ -92,12 +233,51 @@ impl Cpu {
         }
     }
 
-    pub fn execute(&mut self) {
-        let opcode: u16 = read_word(self.memory, self.pc);
-        self.process_opcode(opcode);
+    // does nothing once the program has exited, or while it waits for vblank
+    pub fn execute(&mut self) -> Result<(), CpuError> {
+        if self.exited || self.vblank_wait {
+            return Ok(());
+        }
+        self.poll_key_wait();
+        if self.waiting_for_key() {
+            return Ok(());
+        }
+        let opcode = self.read_word(self.pc as usize, self.pc)?;
+        self.process_opcode(opcode)
+    }
+
+    pub fn waiting_for_key(&self) -> bool {
+        self.key_wait.is_some()
+    }
+
+    // moves a waiting FX0A along with the keypad as it is now; when several
+    // keys go down at once the lowest one wins
+    pub fn poll_key_wait(&mut self) {
+        let keys = self.keypad.keys;
+        self.key_wait = match self.key_wait {
+            Some(KeyWait::Press { register, mut held }) => {
+                for (held, &down) in held.iter_mut().zip(&keys) {
+                    *held &= down;
+                }
+                match (0..keys.len()).find(|&key| keys[key] && !held[key]) {
+                    Some(key) => Some(KeyWait::Release {
+                        register,
+                        key: key as u8,
+                    }),
+                    None => Some(KeyWait::Press { register, held }),
+                }
+            }
+            Some(KeyWait::Release { register, key }) if !keys[key as usize] => {
+                self.v[register] = key;
+                None
+            }
+            key_wait => key_wait,
+        };
     }
 
+    // the 60 Hz tick at the end of a frame, which is also the vertical blank
     pub fn decrement_timers(&mut self) {
+        self.vblank_wait = false;
         if self.dt > 0 {
             self.dt -= 1;
         }
@@ -111,147 +291,220 @@ impl Cpu {
             "PC: 0x{:04X} I: 0x{:04X}\n\
              V: {:02X?}\n\
              Stack: {:04X?} (SP: {})\n\
-             Timers: DT={:02X} ST={:02X}",
+             Timers: DT={:02X} ST={:02X}{}",
             self.pc,
             self.i,
             self.v,
             &self.stack[..self.sp as usize],
             self.sp,
             self.dt,
-            self.st
+            self.st,
+            match self.key_wait {
+                Some(KeyWait::Press { register, .. } | KeyWait::Release { register, .. }) => {
+                    format!("\nWaiting for a key into V{:X}", register)
+                }
+                None => String::new(),
+            }
         )
     }
 
-    fn process_opcode(&mut self, opcode: u16) {
-        // get opcode parameters
-        let x = ((opcode & 0x0F00) >> 8) as usize;
-        let y = ((opcode & 0x00F0) >> 4) as usize;
-        let nnn = opcode & 0x0FFF;
-        let kk = (opcode & 0x00FF) as u8;
-        let n = (opcode & 0x000F) as u8;
-
-        // split into nibbles
-        let op_1 = (opcode & 0xF000) >> 12;
-        let op_2 = (opcode & 0x0F00) >> 8;
-        let op_3 = (opcode & 0x00F0) >> 4;
-        let op_4 = opcode & 0x000F;
+    fn process_opcode(&mut self, opcode: u16) -> Result<(), CpuError> {
+        let instruction = Instruction::decode(opcode).ok_or(CpuError::UnknownOpcode {
+            opcode,
+            pc: self.pc,
+        })?;
 
         // increment program counter
-        self.pc += 2;
-
-        match (op_1, op_2, op_3, op_4) {
-            (0x00, 0x00, 0x0E, 0x00) => self.op_00e0(),
-
-            (0x00, 0x00, 0x0E, 0x0E) => self.op_00ee(),
-
-            (0x01, _, _, _) => self.op_1nnn(nnn),
-
-            (0x02, _, _, _) => self.op_2nnn(nnn),
-
-            (0x03, _, _, _) => self.op_3xkk(x, kk),
-
-            (0x04, _, _, _) => self.op_4xkk(x, kk),
-
-            (0x05, _, _, 0x00) => self.op_5xy0(x, y),
-
-            (0x06, _, _, _) => self.op_6xkk(x, kk),
-
-            (0x07, _, _, _) => self.op_7xkk(x, kk),
-
-            (0x08, _, _, 0x00) => self.op_8xy0(x, y),
-
-            (0x08, _, _, 0x01) => self.op_8xy1(x, y),
-
-            (0x08, _, _, 0x02) => self.op_8xy2(x, y),
-
-            (0x08, _, _, 0x03) => self.op_8xy3(x, y),
-
-            (0x08, _, _, 0x04) => self.op_8xy4(x, y),
-
-            (0x08, _, _, 0x05) => self.op_8xy5(x, y),
-
-            (0x08, _, _, 0x06) => self.op_8xy6(x, y),
-
-            (0x08, _, _, 0x07) => self.op_8xy7(x, y),
-
-            (0x08, _, _, 0x0E) => self.op_8xye(x, y),
-
-            (0x09, _, _, 0x00) => self.op_9xy0(x, y),
-
-            (0x0A, _, _, _) => self.op_annn(nnn),
-
-            (0x0B, _, _, _) => self.op_bnnn(nnn),
-
-            (0x0C, _, _, _) => self.op_cxkk(x, kk),
-
-            (0x0D, _, _, _) => self.op_dxyn(x, y, n),
-
-            (0x0E, _, 0x09, 0x0E) => self.op_ex9e(x),
-
-            (0x0E, _, 0x0A, 0x01) => self.op_exa1(x),
-
-            (0x0F, _, 0x00, 0x07) => self.op_fx07(x),
+        self.pc = self.pc.wrapping_add(2);
+
+        match instruction {
+            Instruction::ScrollDown(n) => self.op_00cn(n),
+            Instruction::ScrollUp(n) => self.op_00dn(n),
+            Instruction::Clear => self.op_00e0(),
+            Instruction::Return => self.op_00ee()?,
+            Instruction::ScrollRight => self.op_00fb(),
+            Instruction::ScrollLeft => self.op_00fc(),
+            Instruction::Exit => self.op_00fd(),
+            Instruction::Lores => self.op_00fe(),
+            Instruction::Hires => self.op_00ff(),
+            Instruction::Jump(nnn) => self.op_1nnn(nnn),
+            Instruction::Call(nnn) => self.op_2nnn(nnn)?,
+            Instruction::SkipEqImm { x, kk } => self.op_3xkk(x, kk)?,
+            Instruction::SkipNeImm { x, kk } => self.op_4xkk(x, kk)?,
+            Instruction::SkipEq { x, y } => self.op_5xy0(x, y)?,
+            Instruction::SaveRange { x, y } => self.op_5xy2(x, y)?,
+            Instruction::LoadRange { x, y } => self.op_5xy3(x, y)?,
+            Instruction::LoadImm { x, kk } => self.op_6xkk(x, kk),
+            Instruction::AddImm { x, kk } => self.op_7xkk(x, kk),
+            Instruction::Move { x, y } => self.op_8xy0(x, y),
+            Instruction::Or { x, y } => self.op_8xy1(x, y),
+            Instruction::And { x, y } => self.op_8xy2(x, y),
+            Instruction::Xor { x, y } => self.op_8xy3(x, y),
+            Instruction::Add { x, y } => self.op_8xy4(x, y),
+            Instruction::Sub { x, y } => self.op_8xy5(x, y),
+            Instruction::ShiftRight { x, y } => self.op_8xy6(x, y),
+            Instruction::SubReverse { x, y } => self.op_8xy7(x, y),
+            Instruction::ShiftLeft { x, y } => self.op_8xye(x, y),
+            Instruction::SkipNe { x, y } => self.op_9xy0(x, y)?,
+            Instruction::LoadI(nnn) => self.op_annn(nnn),
+            Instruction::JumpOffset(nnn) => self.op_bnnn(nnn),
+            Instruction::Random { x, kk } => self.op_cxkk(x, kk),
+            Instruction::Draw { x, y, n } => self.op_dxyn(x, y, n)?,
+            Instruction::SkipKey(x) => self.op_ex9e(x)?,
+            Instruction::SkipNotKey(x) => self.op_exa1(x)?,
+            Instruction::LoadLongI => self.op_f000()?,
+            Instruction::Plane(n) => self.op_fn01(n as usize),
+            Instruction::Audio => self.op_f002()?,
+            Instruction::GetDelay(x) => self.op_fx07(x),
+            Instruction::WaitKey(x) => self.op_fx0a(x),
+            Instruction::SetDelay(x) => self.op_fx15(x),
+            Instruction::SetSound(x) => self.op_fx18(x),
+            Instruction::AddI(x) => self.op_fx1e(x),
+            Instruction::Font(x) => self.op_fx29(x),
+            Instruction::BigFont(x) => self.op_fx30(x),
+            Instruction::Bcd(x) => self.op_fx33(x)?,
+            Instruction::Pitch(x) => self.op_fx3a(x),
+            Instruction::Save(x) => self.op_fx55(x)?,
+            Instruction::Load(x) => self.op_fx65(x)?,
+            Instruction::SaveFlags(x) => self.op_fx75(x),
+            Instruction::LoadFlags(x) => self.op_fx85(x),
+        }
+        self.instructions += 1;
+        self.vip_cycles += instruction.vip_cycles() as u64;
+        Ok(())
+    }
 
-            (0x0F, _, 0x00, 0x0A) => self.op_fx0a(x),
+    // address of the instruction being executed, the pc has already moved past it
+    fn instruction_pc(&self) -> u16 {
+        self.pc.wrapping_sub(2)
+    }
 
-            (0x0F, _, 0x01, 0x05) => self.op_fx15(x),
+    fn read_byte(&mut self, addr: usize) -> Result<u8, CpuError> {
+        if let Some(trace) = self.memory_trace.as_mut() {
+            trace.push((addr, Access::Read));
+        }
+        self.memory
+            .get(addr)
+            .copied()
+            .ok_or(CpuError::MemoryOutOfBounds {
+                addr,
+                pc: self.instruction_pc(),
+            })
+    }
+
+    fn write_byte(&mut self, addr: usize, value: u8) -> Result<(), CpuError> {
+        if let Some(trace) = self.memory_trace.as_mut() {
+            trace.push((addr, Access::Write));
+        }
+        let pc = self.instruction_pc();
+        let byte = self
+            .memory
+            .get_mut(addr)
+            .ok_or(CpuError::MemoryOutOfBounds { addr, pc })?;
+        *byte = value;
+        Ok(())
+    }
+
+    // instruction fetches happen before the pc moves, so they report the pc they were given
+    fn read_word(&self, addr: usize, pc: u16) -> Result<u16, CpuError> {
+        match (self.memory.get(addr), self.memory.get(addr + 1)) {
+            (Some(&high), Some(&low)) => Ok((high as u16) << 8 | low as u16),
+            _ => Err(CpuError::MemoryOutOfBounds { addr: addr + 1, pc }),
+        }
+    }
 
-            (0x0F, _, 0x01, 0x08) => self.op_fx18(x),
+    fn op_00cn(&mut self, n: u8) {
+        self.display.scroll_down(n as usize);
+    }
 
-            (0x0F, _, 0x01, 0x0E) => self.op_fx1e(x),
+    fn op_00dn(&mut self, n: u8) {
+        self.display.scroll_up(n as usize);
+    }
 
-            (0x0F, _, 0x02, 0x09) => self.op_fx29(x),
+    fn op_00e0(&mut self) {
+        self.display.clear_planes();
+    }
 
-            (0x0F, _, 0x03, 0x03) => self.op_fx33(x),
+    fn op_00ee(&mut self) -> Result<(), CpuError> {
+        if self.sp == 0 {
+            return Err(CpuError::StackUnderflow {
+                pc: self.instruction_pc(),
+            });
+        }
+        self.pc = self.stack[(self.sp - 1) as usize];
+        self.sp -= 1;
+        Ok(())
+    }
 
-            (0x0F, _, 0x05, 0x05) => self.op_fx55(x),
+    fn op_00fb(&mut self) {
+        self.display.scroll_right(4);
+    }
 
-            (0x0F, _, 0x06, 0x05) => self.op_fx65(x),
+    fn op_00fc(&mut self) {
+        self.display.scroll_left(4);
+    }
 
-            (_, _, _, _) => (),
-        }
+    fn op_00fd(&mut self) {
+        self.exited = true;
     }
 
-    fn op_00e0(&mut self) {
-        for y in 0..HEIGHT {
-            for x in 0..WIDTH {
-                self.display.memory[y][x] = 0;
-            }
-        }
-        self.display.draw_flag = true;
+    fn op_00fe(&mut self) {
+        self.display.set_hires(false);
     }
 
-    fn op_00ee(&mut self) {
-        self.pc = self.stack[(self.sp - 1) as usize];
-        self.sp -= 1;
+    fn op_00ff(&mut self) {
+        self.display.set_hires(true);
     }
 
     fn op_1nnn(&mut self, nnn: u16) {
         self.pc = nnn;
     }
 
-    fn op_2nnn(&mut self, nnn: u16) {
+    fn op_2nnn(&mut self, nnn: u16) -> Result<(), CpuError> {
+        if self.sp as usize >= self.stack.len() {
+            return Err(CpuError::StackOverflow {
+                pc: self.instruction_pc(),
+            });
+        }
         self.stack[self.sp as usize] = self.pc;
         self.sp += 1;
         self.pc = nnn;
+        Ok(())
     }
 
-    fn op_3xkk(&mut self, x: usize, kk: u8) {
+    fn op_3xkk(&mut self, x: usize, kk: u8) -> Result<(), CpuError> {
         if self.v[x] == kk {
-            self.pc += 2;
+            self.skip()?;
         }
+        Ok(())
     }
 
-    fn op_4xkk(&mut self, x: usize, kk: u8) {
+    fn op_4xkk(&mut self, x: usize, kk: u8) -> Result<(), CpuError> {
         if self.v[x] != kk {
-            self.pc += 2;
+            self.skip()?;
         }
+        Ok(())
     }
 
-    fn op_5xy0(&mut self, x: usize, y: usize) {
+    fn op_5xy0(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
         if self.v[x] == self.v[y] {
-            self.pc += 2;
+            self.skip()?;
+        }
+        Ok(())
+    }
+
+    fn op_5xy2(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
+        for (offset, reg) in register_range(x, y).enumerate() {
+            self.write_byte(self.i as usize + offset, self.v[reg])?;
+        }
+        Ok(())
+    }
+
+    fn op_5xy3(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
+        for (offset, reg) in register_range(x, y).enumerate() {
+            self.v[reg] = self.read_byte(self.i as usize + offset)?;
         }
+        Ok(())
     }
 
     fn op_6xkk(&mut self, x: usize, kk: u8) {
@@ -268,17 +521,23 @@ impl Cpu {
 
     fn op_8xy1(&mut self, x: usize, y: usize) {
         self.v[x] |= self.v[y];
-        self.v[0x0F] = 0;
+        if self.quirks.vf_reset {
+            self.v[0x0F] = 0;
+        }
     }
 
     fn op_8xy2(&mut self, x: usize, y: usize) {
         self.v[x] &= self.v[y];
-        self.v[0x0F] = 0;
+        if self.quirks.vf_reset {
+            self.v[0x0F] = 0;
+        }
     }
 
     fn op_8xy3(&mut self, x: usize, y: usize) {
         self.v[x] ^= self.v[y];
-        self.v[0x0F] = 0;
+        if self.quirks.vf_reset {
+            self.v[0x0F] = 0;
+        }
     }
 
     fn op_8xy4(&mut self, x: usize, y: usize) {
@@ -296,9 +555,13 @@ impl Cpu {
         self.v[0x0F] = if vx >= vy { 1 } else { 0 };
     }
 
-    fn op_8xy6(&mut self, x: usize, _y: usize) {
-        let vx = self.v[x];
-        self.v[x] >>= 1;
+    fn op_8xy6(&mut self, x: usize, y: usize) {
+        let vx = if self.quirks.shift_uses_vy {
+            self.v[y]
+        } else {
+            self.v[x]
+        };
+        self.v[x] = vx >> 1;
         self.v[0x0F] = vx & 1;
     }
 
@@ -307,16 +570,21 @@ impl Cpu {
         self.v[0x0F] = u8::from(self.v[y] > self.v[x]);
     }
 
-    fn op_8xye(&mut self, x: usize, _y: usize) {
-        let vx = self.v[x];
-        self.v[x] <<= 1;
+    fn op_8xye(&mut self, x: usize, y: usize) {
+        let vx = if self.quirks.shift_uses_vy {
+            self.v[y]
+        } else {
+            self.v[x]
+        };
+        self.v[x] = vx << 1;
         self.v[0x0F] = (vx & 0b10000000) >> 7;
     }
 
-    fn op_9xy0(&mut self, x: usize, y: usize) {
+    fn op_9xy0(&mut self, x: usize, y: usize) -> Result<(), CpuError> {
         if self.v[x] != self.v[y] {
-            self.pc += 2;
+            self.skip()?;
         }
+        Ok(())
     }
 
     fn op_annn(&mut self, nnn: u16) {
@@ -324,56 +592,116 @@ impl Cpu {
     }
 
     fn op_bnnn(&mut self, nnn: u16) {
-        self.pc = nnn + self.v[0] as u16;
+        let offset = if self.quirks.jump_uses_vx {
+            self.v[((nnn & 0x0F00) >> 8) as usize]
+        } else {
+            self.v[0]
+        };
+        self.pc = nnn + offset as u16;
     }
 
     fn op_cxkk(&mut self, x: usize, kk: u8) {
-        let mut rng = rand::thread_rng();
-        self.v[x] = rng.gen::<u8>() & kk;
+        self.v[x] = self.rng.next_byte() & kk;
     }
 
-    fn op_dxyn(&mut self, x: usize, y: usize, n: u8) {
+    fn op_dxyn(&mut self, x: usize, y: usize, n: u8) -> Result<(), CpuError> {
+        // DXY0 draws a 16x16 sprite stored as 16 big-endian words
+        let (cols, rows) = if n == 0 { (16, 16) } else { (8, n as usize) };
+        let bytes_per_row = cols / 8;
+        let width = self.display.width();
+        let height = self.display.height();
+        // the starting position always wraps, only the sprite's overhang is clipped
+        let origin_x = self.v[x] as usize % width;
+        let origin_y = self.v[y] as usize % height;
+
+        let row_cycles = if origin_x.is_multiple_of(8) {
+            VIP_SPRITE_ROW_CYCLES
+        } else {
+            VIP_SPRITE_ROW_CYCLES * 2
+        };
+
+        // with several planes selected, each plane's sprite data follows the previous one
+        let mut addr = self.i as usize;
         self.v[0x0F] = 0;
-        for byte in 0..n as usize {
-            let y = (self.v[y] as usize + byte) % HEIGHT;
-            for bit in 0..8 {
-                let x = (self.v[x] as usize + bit) % WIDTH;
-                let pixel = (self.memory[(self.i + byte as u16) as usize] >> (7 - bit)) & 1;
-                self.v[0x0F] |= pixel & self.display.memory[y][x];
-                self.display.memory[y][x] ^= pixel;
+        for plane in [1, 2] {
+            if self.display.planes & plane == 0 {
+                continue;
             }
+            for row in 0..rows {
+                let y = origin_y + row;
+                if y >= height && self.quirks.clip_sprites {
+                    break;
+                }
+                let y = y % height;
+                self.vip_cycles += row_cycles * bytes_per_row as u64;
+                let bits = if cols == 16 {
+                    (self.read_byte(addr + row * 2)? as u16) << 8
+                        | self.read_byte(addr + row * 2 + 1)? as u16
+                } else {
+                    (self.read_byte(addr + row)? as u16) << 8
+                };
+                for col in 0..cols {
+                    let x = origin_x + col;
+                    if x >= width && self.quirks.clip_sprites {
+                        break;
+                    }
+                    let x = x % width;
+                    if (bits >> (15 - col)) & 1 == 1 {
+                        let on = self.display.get_pixel(x, y, plane);
+                        self.v[0x0F] |= on as u8;
+                        self.display.set_pixel(x, y, plane, !on);
+                    }
+                }
+            }
+            addr += rows * bytes_per_row;
         }
         self.display.draw_flag = true;
+        self.vblank_wait = self.quirks.display_wait;
+        Ok(())
     }
 
-    fn op_ex9e(&mut self, x: usize) {
-        self.pc += if self.keypad.is_key_down(self.v[x] as usize) {
-            2
-        } else {
-            0
-        };
+    // only the low nibble of VX picks the key, like the VIP's keypad latch
+    fn op_ex9e(&mut self, x: usize) -> Result<(), CpuError> {
+        if self.keypad.is_key_down((self.v[x] & 0xF) as usize) {
+            self.skip()?;
+        }
+        Ok(())
     }
 
-    fn op_exa1(&mut self, x: usize) {
-        self.pc += if self.keypad.is_key_down(self.v[x] as usize) {
-            0
-        } else {
-            2
-        };
+    fn op_exa1(&mut self, x: usize) -> Result<(), CpuError> {
+        if !self.keypad.is_key_down((self.v[x] & 0xF) as usize) {
+            self.skip()?;
+        }
+        Ok(())
+    }
+
+    fn op_f000(&mut self) -> Result<(), CpuError> {
+        self.i = self.read_word(self.pc as usize, self.instruction_pc())?;
+        self.pc = self.pc.wrapping_add(2);
+        Ok(())
+    }
+
+    fn op_fn01(&mut self, n: usize) {
+        self.display.planes = n as u8 & PLANE_MASK;
+    }
+
+    fn op_f002(&mut self) -> Result<(), CpuError> {
+        for offset in 0..self.audio_pattern.len() {
+            self.audio_pattern[offset] = self.read_byte(self.i as usize + offset)?;
+        }
+        Ok(())
     }
 
     fn op_fx07(&mut self, x: usize) {
         self.v[x] = self.dt;
     }
 
+    // like the COSMAC VIP, VX is only loaded once the key is released
     fn op_fx0a(&mut self, x: usize) {
-        self.pc -= 2;
-        for (i, key) in self.keypad.keys.iter().enumerate() {
-            if *key {
-                self.v[x] = i as u8;
-                self.pc += 2;
-            }
-        }
+        self.key_wait = Some(KeyWait::Press {
+            register: x,
+            held: self.keypad.keys,
+        });
     }
 
     fn op_fx15(&mut self, x: usize) {
@@ -385,7 +713,7 @@ impl Cpu {
     }
 
     fn op_fx1e(&mut self, x: usize) {
-        self.i += self.v[x] as u16;
+        self.i = self.i.wrapping_add(self.v[x] as u16);
         self.v[0x0F] = u8::from(self.i > 0xF00);
     }
 
@@ -393,24 +721,68 @@ impl Cpu {
         self.i = (self.v[x] as u16) * 5;
     }
 
-    fn op_fx33(&mut self, x: usize) {
-        self.memory[self.i as usize] = self.v[x] / 100;
-        self.memory[(self.i + 1) as usize] = (self.v[x] % 100) / 10;
-        self.memory[(self.i + 2) as usize] = self.v[x] % 10;
+    fn op_fx30(&mut self, x: usize) {
+        self.i = (HIRES_FONT_ADDR as u16) + (self.v[x] as u16 & 0x0F) * 10;
+    }
+
+    fn op_fx33(&mut self, x: usize) -> Result<(), CpuError> {
+        let i = self.i as usize;
+        self.write_byte(i, self.v[x] / 100)?;
+        self.write_byte(i + 1, (self.v[x] % 100) / 10)?;
+        self.write_byte(i + 2, self.v[x] % 10)?;
+        // the VIP counts each digit up one at a time
+        let digits = self.v[x] / 100 + (self.v[x] % 100) / 10 + self.v[x] % 10;
+        self.vip_cycles += digits as u64 * VIP_BCD_DIGIT_CYCLES;
+        Ok(())
     }
 
-    fn op_fx55(&mut self, x: usize) {
+    fn op_fx3a(&mut self, x: usize) {
+        self.pitch = self.v[x];
+    }
+
+    fn op_fx55(&mut self, x: usize) -> Result<(), CpuError> {
         for i in 0..(x + 1) {
-            self.memory[(self.i + i as u16) as usize] = self.v[i];
+            self.write_byte(self.i as usize + i, self.v[i])?;
+        }
+        self.advance_i(x);
+        Ok(())
+    }
+
+    // where FX55/FX65 leave I, depending on the interpreter
+    fn advance_i(&mut self, x: usize) {
+        if self.quirks.increment_i {
+            let step = if self.quirks.increment_i_by_x {
+                x
+            } else {
+                x + 1
+            };
+            self.i = self.i.wrapping_add(step as u16);
         }
-        self.i = self.i + x as u16 + 1;
     }
 
-    fn op_fx65(&mut self, x: usize) {
+    fn op_fx65(&mut self, x: usize) -> Result<(), CpuError> {
         for i in 0..(x + 1) {
-            self.v[i] = self.memory[(self.i + i as u16) as usize];
+            self.v[i] = self.read_byte(self.i as usize + i)?;
         }
-        self.i = self.i + x as u16 + 1;
+        self.advance_i(x);
+        Ok(())
+    }
+
+    fn op_fx75(&mut self, x: usize) {
+        self.rpl[..=x].copy_from_slice(&self.v[..=x]);
+    }
+
+    fn op_fx85(&mut self, x: usize) {
+        self.v[..=x].copy_from_slice(&self.rpl[..=x]);
+    }
+
+    // skips the next instruction, stepping over both words of an XO-CHIP F000 NNNN
+    fn skip(&mut self) -> Result<(), CpuError> {
+        let long = self.platform == Platform::XoChip
+            && self.read_word(self.pc as usize, self.instruction_pc())? == 0xF000;
+        self.pc = self.pc.wrapping_add(if long { 4 } else { 2 });
+        self.vip_cycles += VIP_SKIP_CYCLES;
+        Ok(())
     }
 }
 
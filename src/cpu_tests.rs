@@ -1,15 +1,38 @@
 use super::*;
+use crate::display::{HEIGHT, WIDTH};
+use crate::{assembler::assemble, quirks::Profile, rng::RngMode};
 
 fn build_cpu() -> Cpu {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::new(Quirks::default());
     cpu.pc = 0xF00;
     cpu.v = [0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7];
     cpu
 }
 
+fn build_xo_cpu() -> Cpu {
+    let mut cpu = build_cpu();
+    cpu.set_platform(Platform::XoChip);
+    cpu
+}
+
+// assembles an Octo program and runs it until it exits
+fn run_octo(source: &str) -> Cpu {
+    let program = assemble(source).unwrap();
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.reset();
+    cpu.load(&program.rom);
+    for _ in 0..10_000 {
+        if cpu.exited {
+            return cpu;
+        }
+        cpu.execute().unwrap();
+    }
+    panic!("program didn't exit\n{}", cpu.dump_state());
+}
+
 #[test]
 fn test_initial_state() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::new(Quirks::default());
     cpu.reset();
     assert_eq!(cpu.pc, 0x200);
     assert_eq!(cpu.sp, 0);
@@ -23,18 +46,72 @@ fn test_initial_state() {
 
 #[test]
 fn test_load_data() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::new(Quirks::default());
     cpu.load(&[1, 2, 3]);
     assert_eq!(cpu.memory[0x200], 1);
     assert_eq!(cpu.memory[0x201], 2);
     assert_eq!(cpu.memory[0x202], 3);
 }
 
+#[test]
+fn test_platform_memory_size() {
+    let mut cpu = Cpu::new(Quirks::default());
+    assert_eq!(cpu.memory.len(), 0x1000);
+
+    cpu.set_platform(Platform::XoChip);
+    cpu.reset();
+    assert_eq!(cpu.memory.len(), 0x10000);
+
+    cpu.load(&[1, 2, 3]);
+    assert_eq!(cpu.memory[0x202], 3);
+}
+
+#[test]
+fn test_initial_hires_font() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.reset();
+    assert_eq!(
+        cpu.memory[HIRES_FONT_ADDR..HIRES_FONT_ADDR + 10],
+        [0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF]
+    );
+}
+
+#[test]
+fn test_op_00cn() {
+    let mut cpu = build_cpu();
+    cpu.display.memory[0][3] = 1;
+    cpu.process_opcode(0x00C2).unwrap();
+
+    assert_eq!(cpu.display.memory[0][3], 0);
+    assert_eq!(cpu.display.memory[2][3], 1);
+    assert!(cpu.display.draw_flag);
+}
+
+#[test]
+fn test_op_00dn() {
+    let mut cpu = build_cpu();
+    cpu.display.memory[5][3] = 1;
+    cpu.process_opcode(0x00D2).unwrap();
+
+    assert_eq!(cpu.display.memory[5][3], 0);
+    assert_eq!(cpu.display.memory[3][3], 1);
+}
+
+#[test]
+fn test_op_00e0_selected_planes() {
+    let mut cpu = build_xo_cpu();
+    cpu.display.memory[0][0] = 0b11;
+    cpu.display.planes = 0b10;
+    cpu.process_opcode(0x00E0).unwrap();
+
+    assert_eq!(cpu.display.memory[0][0], 0b01);
+}
+
 #[test]
 fn test_op_00e0() {
     let mut cpu = build_cpu();
     cpu.display.memory = [[128; WIDTH]; HEIGHT];
-    cpu.process_opcode(0x00E0);
+    cpu.process_opcode(0x00E0).unwrap();
 
     for y in 0..HEIGHT {
         for x in 0..WIDTH {
@@ -47,19 +124,77 @@ fn test_op_00e0() {
 
 #[test]
 fn test_op_00ee() {
-    let mut cpu = Cpu::new();
+    let mut cpu = Cpu::new(Quirks::default());
     cpu.sp = 7;
     cpu.stack[6] = 0x7777;
-    cpu.process_opcode(0x00EE);
+    cpu.process_opcode(0x00EE).unwrap();
 
     assert_eq!(cpu.sp, 6);
     assert_eq!(cpu.pc, 0x7777);
 }
 
+#[test]
+fn test_op_00fb() {
+    let mut cpu = build_cpu();
+    cpu.display.memory[1][0] = 1;
+    cpu.display.memory[1][63] = 1;
+    cpu.process_opcode(0x00FB).unwrap();
+
+    assert_eq!(cpu.display.memory[1][0], 0);
+    assert_eq!(cpu.display.memory[1][4], 1);
+    // pixels pushed off the lo-res screen are dropped
+    assert_eq!(cpu.display.memory[1][67], 0);
+}
+
+#[test]
+fn test_op_00fc() {
+    let mut cpu = build_cpu();
+    cpu.display.memory[1][0] = 1;
+    cpu.display.memory[1][6] = 1;
+    cpu.process_opcode(0x00FC).unwrap();
+
+    assert_eq!(cpu.display.memory[1][2], 1);
+    assert_eq!(cpu.display.memory[1][6], 0);
+    assert_eq!(cpu.display.memory[1].iter().filter(|&&p| p == 1).count(), 1);
+}
+
+#[test]
+fn test_op_00fd() {
+    let mut cpu = build_cpu();
+    cpu.memory[0xF00] = 0x00;
+    cpu.memory[0xF01] = 0xFD;
+    cpu.execute().unwrap();
+
+    assert!(cpu.exited);
+    assert_eq!(cpu.pc, (0xF00 + 2));
+
+    // once exited, the interpreter no longer advances
+    cpu.execute().unwrap();
+    assert_eq!(cpu.pc, (0xF00 + 2));
+}
+
+#[test]
+fn test_op_00fe_00ff() {
+    let mut cpu = build_cpu();
+    cpu.process_opcode(0x00FF).unwrap();
+
+    assert!(cpu.display.hires);
+    assert_eq!(cpu.display.width(), 128);
+    assert_eq!(cpu.display.height(), 64);
+
+    cpu.display.memory[10][100] = 1;
+    cpu.process_opcode(0x00FE).unwrap();
+
+    assert!(!cpu.display.hires);
+    assert_eq!(cpu.display.width(), 64);
+    assert_eq!(cpu.display.height(), 32);
+    assert_eq!(cpu.display.memory[10][100], 0);
+}
+
 #[test]
 fn test_op_1nnn() {
-    let mut cpu = Cpu::new();
-    cpu.process_opcode(0x1777);
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.process_opcode(0x1777).unwrap();
 
     assert_eq!(cpu.pc, 0x0777);
 }
@@ -67,7 +202,7 @@ fn test_op_1nnn() {
 #[test]
 fn test_op_2nnn() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0x2777);
+    cpu.process_opcode(0x2777).unwrap();
 
     assert_eq!(cpu.pc, 0x0777);
     assert_eq!(cpu.sp, 1);
@@ -77,11 +212,11 @@ fn test_op_2nnn() {
 #[test]
 fn test_op_3xkk() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0x3201);
+    cpu.process_opcode(0x3201).unwrap();
 
     assert_eq!(cpu.pc, (0xF00 + 4));
 
-    cpu.process_opcode(0x3200);
+    cpu.process_opcode(0x3200).unwrap();
 
     assert_eq!(cpu.pc, (0xF04 + 2));
 }
@@ -89,11 +224,11 @@ fn test_op_3xkk() {
 #[test]
 fn test_op_4xkk() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0x4200);
+    cpu.process_opcode(0x4200).unwrap();
 
     assert_eq!(cpu.pc, (0xF00 + 4));
 
-    cpu.process_opcode(0x4201);
+    cpu.process_opcode(0x4201).unwrap();
 
     assert_eq!(cpu.pc, (0xF04 + 2));
 }
@@ -101,19 +236,47 @@ fn test_op_4xkk() {
 #[test]
 fn test_op_5xy0() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0x5540);
+    cpu.process_opcode(0x5540).unwrap();
 
     assert_eq!(cpu.pc, (0xF00 + 4));
 
-    cpu.process_opcode(0x5500);
+    cpu.process_opcode(0x5500).unwrap();
 
     assert_eq!(cpu.pc, (0xF04 + 2));
 }
 
+#[test]
+fn test_op_5xy2() {
+    let mut cpu = build_xo_cpu();
+    cpu.i = 0x300;
+    cpu.process_opcode(0x5242).unwrap();
+
+    assert_eq!(cpu.memory[0x300..0x303], [1, 1, 2]);
+    assert_eq!(cpu.memory[0x303], 0);
+    assert_eq!(cpu.i, 0x300);
+
+    // a descending range stores the registers in reverse
+    cpu.process_opcode(0x5422).unwrap();
+
+    assert_eq!(cpu.memory[0x300..0x303], [2, 1, 1]);
+}
+
+#[test]
+fn test_op_5xy3() {
+    let mut cpu = build_xo_cpu();
+    cpu.i = 0x300;
+    cpu.memory[0x300..0x303].copy_from_slice(&[0xAA, 0xBB, 0xCC]);
+    cpu.process_opcode(0x5133).unwrap();
+
+    assert_eq!(cpu.v[1..4], [0xAA, 0xBB, 0xCC]);
+    assert_eq!(cpu.v[4], 2);
+    assert_eq!(cpu.i, 0x300);
+}
+
 #[test]
 fn test_op_6xkk() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0x6577);
+    cpu.process_opcode(0x6577).unwrap();
 
     assert_eq!(cpu.v[5], 0x77);
     assert_eq!(cpu.pc, (0xF00 + 2));
@@ -122,7 +285,7 @@ fn test_op_6xkk() {
 #[test]
 fn test_op_7xkk() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0x75f0);
+    cpu.process_opcode(0x75f0).unwrap();
 
     assert_eq!(cpu.v[5], 0xf2);
     assert_eq!(cpu.pc, (0xF00 + 2));
@@ -131,7 +294,7 @@ fn test_op_7xkk() {
 #[test]
 fn test_op_8xy0() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0x8050);
+    cpu.process_opcode(0x8050).unwrap();
 
     assert_eq!(cpu.v[0], 0x02);
     assert_eq!(cpu.pc, (0xF00 + 2));
@@ -140,17 +303,87 @@ fn test_op_8xy0() {
 #[test]
 fn test_op_8xy1() {
     let mut cpu = build_cpu();
-    todo!();
+    cpu.v[0x0F] = 1;
+    cpu.process_opcode(0x8241).unwrap();
+
+    assert_eq!(cpu.v[2], 3);
+    assert_eq!(cpu.v[0x0F], 0);
+    assert_eq!(cpu.pc, (0xF00 + 2));
+}
+
+#[test]
+fn test_op_8xy1_without_vf_reset() {
+    let mut cpu = build_cpu();
+    cpu.quirks.vf_reset = false;
+    cpu.v[0x0F] = 1;
+    cpu.process_opcode(0x8241).unwrap();
+
+    assert_eq!(cpu.v[2], 3);
+    assert_eq!(cpu.v[0x0F], 1);
+}
+
+#[test]
+fn test_op_8xy2() {
+    let mut cpu = build_cpu();
+    cpu.v[0x0F] = 1;
+    cpu.process_opcode(0x8652).unwrap();
+
+    assert_eq!(cpu.v[6], 2);
+    assert_eq!(cpu.v[0x0F], 0);
+}
+
+#[test]
+fn test_op_8xy3() {
+    let mut cpu = build_cpu();
+    cpu.v[0x0F] = 1;
+    cpu.process_opcode(0x8643).unwrap();
+
+    assert_eq!(cpu.v[6], 1);
+    assert_eq!(cpu.v[0x0F], 0);
+}
+
+#[test]
+fn test_op_8xy6() {
+    let mut cpu = build_cpu();
+    cpu.v[1] = 0b0000_0101;
+    cpu.process_opcode(0x8016).unwrap();
+
+    assert_eq!(cpu.v[0], 0b0000_0010);
+    assert_eq!(cpu.v[0x0F], 1);
+
+    cpu.quirks.shift_uses_vy = false;
+    cpu.v[0] = 0b0000_0110;
+    cpu.process_opcode(0x8016).unwrap();
+
+    assert_eq!(cpu.v[0], 0b0000_0011);
+    assert_eq!(cpu.v[0x0F], 0);
+}
+
+#[test]
+fn test_op_8xye() {
+    let mut cpu = build_cpu();
+    cpu.v[1] = 0b1000_0001;
+    cpu.process_opcode(0x801E).unwrap();
+
+    assert_eq!(cpu.v[0], 0b0000_0010);
+    assert_eq!(cpu.v[0x0F], 1);
+
+    cpu.quirks.shift_uses_vy = false;
+    cpu.v[0] = 0b0100_0000;
+    cpu.process_opcode(0x801E).unwrap();
+
+    assert_eq!(cpu.v[0], 0b1000_0000);
+    assert_eq!(cpu.v[0x0F], 0);
 }
 
 #[test]
 fn test_op_9xy0() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0x90E0);
+    cpu.process_opcode(0x90E0).unwrap();
 
     assert_eq!(cpu.pc, (0xF00 + 4));
 
-    cpu.process_opcode(0x9010);
+    cpu.process_opcode(0x9010).unwrap();
 
     assert_eq!(cpu.pc, (0xF04 + 2));
 }
@@ -158,7 +391,7 @@ fn test_op_9xy0() {
 #[test]
 fn test_op_annn() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xA123);
+    cpu.process_opcode(0xA123).unwrap();
 
     assert_eq!(cpu.i, 0x123);
 }
@@ -166,26 +399,131 @@ fn test_op_annn() {
 #[test]
 fn test_op_bnnn() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xB123);
+    cpu.process_opcode(0xB123).unwrap();
 
     assert_eq!(cpu.pc, 0x123);
 }
 
+#[test]
+fn test_op_bnnn_jump_uses_vx() {
+    let mut cpu = build_cpu();
+    cpu.quirks.jump_uses_vx = true;
+    cpu.process_opcode(0xB523).unwrap();
+
+    assert_eq!(cpu.pc, 0x525);
+}
+
 #[test]
 fn test_op_cxkk() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xc000);
+    cpu.process_opcode(0xc000).unwrap();
 
     assert_eq!(cpu.v[0], 0);
 
-    cpu.process_opcode(0xc00f);
+    cpu.process_opcode(0xc00f).unwrap();
 
     assert_eq!(cpu.v[0] & 0xf0, 0);
+
+    // the same seed gives the same numbers
+    let mut expected = RngMode::Seeded.create(1234);
+    cpu.rng = RngMode::Seeded.create(1234);
+    for _ in 0..8 {
+        cpu.process_opcode(0xc1ff).unwrap();
+        assert_eq!(cpu.v[1], expected.next_byte());
+    }
 }
 
 #[test]
 fn test_op_dxyn() {
-    todo!();
+    let mut cpu = build_cpu();
+    cpu.quirks.clip_sprites = false;
+    cpu.i = 0x300;
+    cpu.memory[0x300] = 0b1100_0001;
+    cpu.memory[0x301] = 0b1000_0000;
+    cpu.v[0] = 62;
+    cpu.v[1] = 31;
+    cpu.process_opcode(0xD012).unwrap();
+
+    assert_eq!(cpu.v[0x0F], 0);
+    assert_eq!(cpu.display.memory[31][62], 1);
+    assert_eq!(cpu.display.memory[31][63], 1);
+    // sprites wrap around both edges of the screen
+    assert_eq!(cpu.display.memory[31][5], 1);
+    assert_eq!(cpu.display.memory[0][62], 1);
+    assert!(cpu.display.draw_flag);
+
+    cpu.v[0] = 62;
+    cpu.v[1] = 31;
+    cpu.process_opcode(0xD012).unwrap();
+
+    assert_eq!(cpu.v[0x0F], 1);
+    assert_eq!(cpu.display.memory[31][62], 0);
+    assert_eq!(cpu.display.memory[0][62], 0);
+}
+
+#[test]
+fn test_op_dxyn_clip_sprites() {
+    let mut cpu = build_cpu();
+    cpu.i = 0x300;
+    cpu.memory[0x300] = 0xFF;
+    cpu.memory[0x301] = 0xFF;
+    cpu.v[0] = 60;
+    cpu.v[1] = 31;
+    cpu.process_opcode(0xD012).unwrap();
+
+    assert_eq!(cpu.display.memory[31][60..64], [1, 1, 1, 1]);
+    assert_eq!(cpu.display.memory[31][0..4], [0, 0, 0, 0]);
+    assert_eq!(cpu.display.memory[0][60], 0);
+
+    // the starting position still wraps around
+    cpu.v[0] = 64 + 2;
+    cpu.v[1] = 32 + 2;
+    cpu.process_opcode(0xD011).unwrap();
+
+    assert_eq!(cpu.display.memory[2][2..10], [1; 8]);
+}
+
+#[test]
+fn test_op_dxy0() {
+    let mut cpu = build_cpu();
+    cpu.process_opcode(0x00FF).unwrap();
+    cpu.i = 0x300;
+    for row in 0..16 {
+        cpu.memory[0x300 + row * 2] = 0x80;
+        cpu.memory[0x300 + row * 2 + 1] = 0x01;
+    }
+    cpu.v[0] = 100;
+    cpu.v[1] = 40;
+    cpu.process_opcode(0xD010).unwrap();
+
+    for row in 40..56 {
+        assert_eq!(cpu.display.memory[row][100], 1);
+        assert_eq!(cpu.display.memory[row][101], 0);
+        assert_eq!(cpu.display.memory[row][115], 1);
+    }
+    assert_eq!(cpu.display.memory[56][100], 0);
+    assert_eq!(cpu.v[0x0F], 0);
+}
+
+#[test]
+fn test_op_dxyn_planes() {
+    let mut cpu = build_xo_cpu();
+    cpu.i = 0x300;
+    cpu.memory[0x300] = 0x80;
+    cpu.memory[0x301] = 0x40;
+    cpu.process_opcode(0xF301).unwrap();
+    cpu.process_opcode(0xD001).unwrap();
+
+    // plane 1 data comes first, plane 2 data follows it
+    assert_eq!(cpu.display.memory[0][0], 0b01);
+    assert_eq!(cpu.display.memory[0][1], 0b10);
+
+    cpu.process_opcode(0xF201).unwrap();
+    cpu.i = 0x301;
+    cpu.process_opcode(0xD001).unwrap();
+
+    assert_eq!(cpu.v[0x0F], 1);
+    assert_eq!(cpu.display.memory[0][1], 0);
 }
 
 #[test]
@@ -193,11 +531,11 @@ fn test_op_ex9e() {
     let mut cpu = build_cpu();
     cpu.keypad.keys[9] = true;
     cpu.v[5] = 9;
-    cpu.process_opcode(0xe59e);
+    cpu.process_opcode(0xe59e).unwrap();
 
     assert_eq!(cpu.pc, (0xF00 + 4));
 
-    cpu.process_opcode(0xe19e);
+    cpu.process_opcode(0xe19e).unwrap();
 
     assert_eq!(cpu.pc, (0xF04 + 2));
 }
@@ -207,20 +545,87 @@ fn test_op_exa1() {
     let mut cpu = build_cpu();
     cpu.keypad.keys[9] = true;
     cpu.v[5] = 9;
-    cpu.process_opcode(0xe5a1);
+    cpu.process_opcode(0xe5a1).unwrap();
 
     assert_eq!(cpu.pc, (0xF00 + 2));
 
-    cpu.process_opcode(0xe1a1);
+    cpu.process_opcode(0xe1a1).unwrap();
 
     assert_eq!(cpu.pc, (0xF02 + 4));
 }
 
+#[test]
+fn test_key_skips_mask_vx() {
+    let mut cpu = build_cpu();
+    cpu.keypad.keys[0] = true;
+    // 0x20 reads key 0 instead of indexing past the keypad
+    cpu.v[5] = 0x20;
+    cpu.process_opcode(0xe59e).unwrap();
+    assert_eq!(cpu.pc, (0xF00 + 4));
+
+    cpu.process_opcode(0xe5a1).unwrap();
+    assert_eq!(cpu.pc, (0xF04 + 2));
+}
+
+#[test]
+fn test_skip_long_instruction() {
+    let mut cpu = build_xo_cpu();
+    cpu.memory[0xF02] = 0xF0;
+    cpu.memory[0xF03] = 0x00;
+    cpu.process_opcode(0x3201).unwrap();
+
+    assert_eq!(cpu.pc, (0xF00 + 6));
+
+    // plain CHIP-8 doesn't know about F000 and only skips one word
+    let mut cpu = build_cpu();
+    cpu.memory[0xF02] = 0xF0;
+    cpu.memory[0xF03] = 0x00;
+    cpu.process_opcode(0x3201).unwrap();
+
+    assert_eq!(cpu.pc, (0xF00 + 4));
+}
+
+#[test]
+fn test_op_f000() {
+    let mut cpu = build_xo_cpu();
+    cpu.memory[0xF02] = 0xAB;
+    cpu.memory[0xF03] = 0xCD;
+    cpu.process_opcode(0xF000).unwrap();
+
+    assert_eq!(cpu.i, 0xABCD);
+    assert_eq!(cpu.pc, (0xF00 + 4));
+}
+
+#[test]
+fn test_op_fn01() {
+    let mut cpu = build_xo_cpu();
+    cpu.process_opcode(0xF201).unwrap();
+
+    assert_eq!(cpu.display.planes, 2);
+
+    cpu.process_opcode(0xF301).unwrap();
+
+    assert_eq!(cpu.display.planes, 3);
+}
+
+#[test]
+fn test_op_f002() {
+    let mut cpu = build_xo_cpu();
+    cpu.i = 0x300;
+    for offset in 0..16 {
+        cpu.memory[0x300 + offset] = offset as u8;
+    }
+    cpu.process_opcode(0xF002).unwrap();
+
+    assert_eq!(cpu.audio_pattern[0], 0);
+    assert_eq!(cpu.audio_pattern[15], 15);
+}
+
 #[test]
 fn test_op_fx07() {
     let mut cpu = build_cpu();
     cpu.dt = 20;
-    cpu.process_opcode(0xf507);
+    cpu.process_opcode(0xf507).unwrap();
 
     assert_eq!(cpu.v[5], 20);
     assert_eq!(cpu.pc, (0xF00 + 2));
@@ -229,17 +634,68 @@ fn test_op_fx07() {
 #[test]
 fn test_op_fx0a() {
     let mut cpu = build_cpu();
+    cpu.process_opcode(0xf00a).unwrap();
+    assert_eq!(cpu.pc, (0xF00 + 2));
+    assert!(cpu.waiting_for_key());
+
     cpu.keypad.keys[9] = true;
-    cpu.process_opcode(0xf00a);
+    cpu.poll_key_wait();
+    assert_eq!(
+        cpu.key_wait,
+        Some(KeyWait::Release {
+            register: 0,
+            key: 9
+        })
+    );
+    assert_eq!(cpu.v[0], 0);
 
+    cpu.keypad.keys[9] = false;
+    cpu.poll_key_wait();
+    assert!(!cpu.waiting_for_key());
     assert_eq!(cpu.v[0], 9);
-    assert_eq!(cpu.pc, (0xF00 + 2));
+}
+
+#[test]
+fn test_op_fx0a_held_keys() {
+    let mut cpu = build_cpu();
+    // held before FX0A ran, so it doesn't count until it's pressed again
+    cpu.keypad.keys[3] = true;
+    cpu.process_opcode(0xf50a).unwrap();
+    cpu.poll_key_wait();
+    assert!(matches!(cpu.key_wait, Some(KeyWait::Press { .. })));
+
+    cpu.keypad.keys[3] = false;
+    cpu.poll_key_wait();
+    cpu.keypad.keys[3] = true;
+    // the lowest of two keys pressed together
+    cpu.keypad.keys[0xC] = true;
+    cpu.poll_key_wait();
+    cpu.keypad.keys[3] = false;
+    cpu.poll_key_wait();
+    assert_eq!(cpu.v[5], 3);
+}
+
+#[test]
+fn test_fx0a_holds_execute() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.reset();
+    // wait for a key into V1, then V0 = 1
+    cpu.load(&[0xF1, 0x0A, 0x60, 0x01]);
+    cpu.execute().unwrap();
+    cpu.execute().unwrap();
+    assert_eq!((cpu.pc, cpu.v[0], cpu.instructions), (0x202, 0, 1));
+
+    cpu.keypad.keys[7] = true;
+    cpu.execute().unwrap();
+    cpu.keypad.keys[7] = false;
+    cpu.execute().unwrap();
+    assert_eq!((cpu.v[0], cpu.v[1]), (1, 7));
 }
 
 #[test]
 fn test_op_fx15() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xf115);
+    cpu.process_opcode(0xf115).unwrap();
 
     assert_eq!(cpu.dt, 0);
 }
@@ -247,7 +703,7 @@ fn test_op_fx15() {
 #[test]
 fn test_op_fx18() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xf118);
+    cpu.process_opcode(0xf118).unwrap();
 
     assert_eq!(cpu.st, 0);
 }
@@ -255,7 +711,7 @@ fn test_op_fx18() {
 #[test]
 fn test_op_fx1e() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xf51e);
+    cpu.process_opcode(0xf51e).unwrap();
 
     assert_eq!(cpu.i, 2);
 }
@@ -263,15 +719,31 @@ fn test_op_fx1e() {
 #[test]
 fn test_op_fx29() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xf529);
+    cpu.process_opcode(0xf529).unwrap();
+
+    assert_eq!(cpu.i, 10);
+}
+
+#[test]
+fn test_op_fx30() {
+    let mut cpu = build_cpu();
+    cpu.process_opcode(0xf530).unwrap();
 
-    todo!();
+    assert_eq!(cpu.i, (HIRES_FONT_ADDR + 20) as u16);
+}
+
+#[test]
+fn test_op_fx3a() {
+    let mut cpu = build_xo_cpu();
+    cpu.process_opcode(0xF73A).unwrap();
+
+    assert_eq!(cpu.pitch, 3);
 }
 
 #[test]
 fn test_op_fx33() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xf533);
+    cpu.process_opcode(0xf533).unwrap();
 
     assert_eq!(cpu.memory[cpu.i as usize], 0);
     assert_eq!(cpu.memory[(cpu.i + 1) as usize], 0);
@@ -281,7 +753,8 @@ fn test_op_fx33() {
 #[test]
 fn test_op_fx55() {
     let mut cpu = build_cpu();
-    cpu.process_opcode(0xf555);
+    cpu.quirks.increment_i = false;
+    cpu.process_opcode(0xf555).unwrap();
 
     assert_eq!(cpu.memory[cpu.i as usize], cpu.v[0]);
     assert_eq!(cpu.memory[(cpu.i + 1) as usize], cpu.v[1]);
@@ -291,11 +764,248 @@ fn test_op_fx55() {
     assert_eq!(cpu.memory[(cpu.i + 5) as usize], cpu.v[5]);
 }
 
+#[test]
+fn test_op_fx55_increment_i() {
+    let mut cpu = build_cpu();
+    cpu.i = 0x300;
+    cpu.process_opcode(0xf555).unwrap();
+
+    assert_eq!(cpu.i, 0x306);
+    assert_eq!(cpu.memory[0x305], cpu.v[5]);
+
+    cpu.quirks = Profile::SuperChipModern.quirks();
+    cpu.process_opcode(0xf565).unwrap();
+
+    assert_eq!(cpu.i, 0x306);
+
+    cpu.quirks = Profile::Chip48.quirks();
+    cpu.process_opcode(0xf565).unwrap();
+
+    assert_eq!(cpu.i, 0x30B);
+}
+
 #[test]
 fn test_op_fx65() {
     let mut cpu = build_cpu();
     cpu.memory[cpu.i as usize] = 7;
-    cpu.process_opcode(0xf065);
+    cpu.process_opcode(0xf065).unwrap();
 
     assert_eq!(cpu.v[0], 7);
 }
+
+#[test]
+fn test_op_fx75_fx85() {
+    let mut cpu = build_cpu();
+    cpu.process_opcode(0xf775).unwrap();
+
+    assert_eq!(cpu.rpl[0..8], [0, 0, 1, 1, 2, 2, 3, 3]);
+    assert_eq!(cpu.rpl[8], 0);
+
+    cpu.v = [0xFF; 16];
+    cpu.process_opcode(0xf385).unwrap();
+
+    assert_eq!(cpu.v[0..5], [0, 0, 1, 1, 0xFF]);
+}
+
+#[test]
+fn test_stack_underflow() {
+    let mut cpu = build_cpu();
+
+    assert_eq!(
+        cpu.process_opcode(0x00EE),
+        Err(CpuError::StackUnderflow { pc: 0xF00 })
+    );
+    assert_eq!(cpu.sp, 0);
+}
+
+#[test]
+fn test_stack_overflow() {
+    let mut cpu = build_cpu();
+    for _ in 0..16 {
+        cpu.process_opcode(0x2F00).unwrap();
+    }
+
+    assert_eq!(
+        cpu.process_opcode(0x2F00),
+        Err(CpuError::StackOverflow { pc: 0xF00 })
+    );
+    assert_eq!(cpu.sp, 16);
+}
+
+#[test]
+fn test_memory_out_of_bounds() {
+    let mut cpu = build_cpu();
+    cpu.i = 0xFFE;
+
+    assert_eq!(
+        cpu.process_opcode(0xF255),
+        Err(CpuError::MemoryOutOfBounds {
+            addr: 0x1000,
+            pc: 0xF00
+        })
+    );
+
+    cpu.pc = 0xF00;
+    assert_eq!(
+        cpu.process_opcode(0xF033),
+        Err(CpuError::MemoryOutOfBounds {
+            addr: 0x1000,
+            pc: 0xF00
+        })
+    );
+
+    cpu.pc = 0xF00;
+    cpu.i = 0xFFC;
+    assert_eq!(
+        cpu.process_opcode(0xD015),
+        Err(CpuError::MemoryOutOfBounds {
+            addr: 0x1000,
+            pc: 0xF00
+        })
+    );
+}
+
+#[test]
+fn test_fetch_out_of_bounds() {
+    let mut cpu = build_cpu();
+    cpu.pc = 0xFFF;
+
+    assert_eq!(
+        cpu.execute(),
+        Err(CpuError::MemoryOutOfBounds {
+            addr: 0x1000,
+            pc: 0xFFF
+        })
+    );
+}
+
+#[test]
+fn test_unknown_opcode() {
+    let mut cpu = build_cpu();
+
+    assert_eq!(
+        cpu.process_opcode(0xE1FF),
+        Err(CpuError::UnknownOpcode {
+            opcode: 0xE1FF,
+            pc: 0xF00
+        })
+    );
+}
+
+#[test]
+fn test_octo_loop() {
+    // sums 1 to 10
+    let cpu = run_octo(
+        "
+        v0 := 0
+        v1 := 1
+        loop
+            v0 += v1
+            v1 += 1
+            while v1 != 11
+        again
+        exit
+        ",
+    );
+
+    assert_eq!(cpu.v[0], 55);
+}
+
+#[test]
+fn test_octo_subroutine_and_memory() {
+    let cpu = run_octo(
+        "
+        : main
+            v0 := 137
+            to-digits
+            i := digits
+            load v2
+            exit
+
+        : to-digits
+            i := digits
+            bcd v0
+            return
+
+        : digits
+            0 0 0
+        ",
+    );
+
+    assert_eq!(cpu.v[0..3], [1, 3, 7]);
+    assert_eq!(cpu.sp, 0);
+}
+
+#[test]
+fn test_octo_carry_flag() {
+    let cpu = run_octo(
+        "
+        v0 := 200
+        v1 := 100
+        v0 += v1
+        if vf == 1 begin
+            v2 := 1
+        else
+            v2 := 2
+        end
+        exit
+        ",
+    );
+
+    assert_eq!(cpu.v[0], 44);
+    assert_eq!(cpu.v[2], 1);
+}
+
+#[test]
+fn test_vip_cycles() {
+    let mut cpu = build_cpu();
+    cpu.process_opcode(0x6005).unwrap();
+    assert_eq!((cpu.instructions, cpu.vip_cycles), (1, 46));
+
+    // a taken skip costs a little more than one that isn't
+    let mut cpu = build_cpu();
+    cpu.process_opcode(0x3000).unwrap();
+    cpu.process_opcode(0x3001).unwrap();
+    assert_eq!(cpu.vip_cycles, 50 + 54);
+
+    // BCD of 137 counts 1 + 3 + 7 digits
+    let mut cpu = build_cpu();
+    cpu.i = 0x300;
+    cpu.v[0] = 137;
+    cpu.process_opcode(0xF033).unwrap();
+    assert_eq!(cpu.vip_cycles, 124 + 11 * 16);
+}
+
+#[test]
+fn test_vip_draw_cycles() {
+    // a 5 row sprite, byte aligned and then not
+    let mut cpu = build_cpu();
+    cpu.process_opcode(0xD015).unwrap();
+    assert_eq!(cpu.vip_cycles, 66 + 5 * 34);
+
+    let mut cpu = build_cpu();
+    cpu.process_opcode(0xD235).unwrap();
+    assert_eq!(cpu.vip_cycles, 66 + 5 * 68);
+}
+
+#[test]
+fn test_display_wait() {
+    let mut cpu = Cpu::new(Quirks::default());
+    cpu.reset();
+    // draw, then V0 = 1
+    cpu.load(&[0xD0, 0x05, 0x60, 0x01]);
+    cpu.execute().unwrap();
+    assert!(cpu.vblank_wait);
+    cpu.execute().unwrap();
+    assert_eq!((cpu.pc, cpu.v[0]), (0x202, 0));
+
+    // the end of the frame lets it carry on
+    cpu.decrement_timers();
+    cpu.execute().unwrap();
+    assert_eq!(cpu.v[0], 1);
+
+    cpu.quirks.display_wait = false;
+    cpu.pc = 0x200;
+    cpu.execute().unwrap();
+    assert!(!cpu.vblank_wait);
+}
@@ -0,0 +1,426 @@
+use std::{collections::BTreeSet, fmt, str::FromStr};
+
+use crate::{
+    cpu::{Access, CpuError},
+    instruction::Instruction,
+    Machine,
+};
+
+pub const HELP: &str = "\
+break <addr>         stop when the pc reaches addr (alias: b)
+delete <addr>        remove a breakpoint (alias: d)
+watch <target>       stop when target is written, target is an address or v0-vf
+rwatch <target>      stop when target is read
+awatch <target>      stop when target is read or written
+unwatch <target>     remove a watchpoint
+step                 execute one instruction (alias: s)
+next                 step, running over subroutine calls (alias: n)
+finish               run until the current subroutine returns
+continue             resume execution (alias: c)
+pause                stop execution (alias: p)
+draw                 run until the end of the next frame that draws
+regs                 print the cpu state (alias: r)
+x <addr> [len]       dump memory
+info                 list breakpoints and watchpoints
+help                 show this message (alias: h)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchTarget {
+    Memory(usize),
+    Register(usize),
+}
+
+impl fmt::Display for WatchTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchTarget::Memory(addr) => write!(f, "0x{:04X}", addr),
+            WatchTarget::Register(reg) => write!(f, "V{:X}", reg),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+impl WatchKind {
+    fn matches(self, access: Access) -> bool {
+        matches!(
+            (self, access),
+            (WatchKind::Access, _)
+                | (WatchKind::Read, Access::Read)
+                | (WatchKind::Write, Access::Write)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub target: WatchTarget,
+    pub kind: WatchKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Breakpoint {
+        pc: u16,
+    },
+    Watchpoint {
+        target: WatchTarget,
+        access: Access,
+        pc: u16,
+    },
+    // a step, next or finish has completed
+    Step {
+        pc: u16,
+    },
+    // the frame requested by `draw` has finished
+    Frame,
+    Error(CpuError),
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StopReason::Breakpoint { pc } => write!(f, "breakpoint at 0x{:04X}", pc),
+            StopReason::Watchpoint { target, access, pc } => {
+                let access = match access {
+                    Access::Read => "read",
+                    Access::Write => "written",
+                };
+                write!(f, "{} {} by instruction at 0x{:04X}", target, access, pc)
+            }
+            StopReason::Step { pc } => write!(f, "stopped at 0x{:04X}", pc),
+            StopReason::Frame => write!(f, "frame drawn"),
+            StopReason::Error(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    Break(u16),
+    Delete(u16),
+    Watch(Watchpoint),
+    Unwatch(WatchTarget),
+    Step,
+    Next,
+    Finish,
+    Continue,
+    Pause,
+    Draw,
+    Regs,
+    Examine { addr: usize, len: usize },
+    Info,
+    Help,
+}
+
+// numbers are hex, like every address the emulator prints
+fn parse_hex(s: &str) -> Result<usize, String> {
+    let digits = s.trim_start_matches("0x").trim_start_matches("0X");
+    usize::from_str_radix(digits, 16).map_err(|_| format!("invalid hex number `{}`", s))
+}
+
+// a program counter value, which can't go past 0xFFFF
+fn parse_addr(s: &str) -> Result<u16, String> {
+    u16::try_from(parse_hex(s)?).map_err(|_| format!("address `{}` is past 0xFFFF", s))
+}
+
+fn parse_target(s: &str) -> Result<WatchTarget, String> {
+    match s.strip_prefix(['v', 'V']) {
+        Some(reg) if reg.len() == 1 => Ok(WatchTarget::Register(parse_hex(reg)?)),
+        _ => Ok(WatchTarget::Memory(parse_hex(s)?)),
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Command, String> {
+        let mut words = line.split_whitespace();
+        let name = words.next().ok_or("empty command")?;
+        let mut arg = || words.next().ok_or(format!("`{}` needs an argument", name));
+
+        let command = match name {
+            "break" | "b" => Command::Break(parse_addr(arg()?)?),
+            "delete" | "d" => Command::Delete(parse_addr(arg()?)?),
+            "watch" | "rwatch" | "awatch" => Command::Watch(Watchpoint {
+                target: parse_target(arg()?)?,
+                kind: match name {
+                    "watch" => WatchKind::Write,
+                    "rwatch" => WatchKind::Read,
+                    _ => WatchKind::Access,
+                },
+            }),
+            "unwatch" => Command::Unwatch(parse_target(arg()?)?),
+            "step" | "s" => Command::Step,
+            "next" | "n" => Command::Next,
+            "finish" => Command::Finish,
+            "continue" | "c" => Command::Continue,
+            "pause" | "p" => Command::Pause,
+            "draw" => Command::Draw,
+            "regs" | "r" => Command::Regs,
+            "x" => {
+                let addr = parse_hex(arg()?)?;
+                let len = match words.next() {
+                    Some(len) => parse_hex(len)?,
+                    None => 0x10,
+                };
+                Command::Examine { addr, len }
+            }
+            "info" => Command::Info,
+            "help" | "h" => Command::Help,
+            _ => return Err(format!("unknown command `{}`, try `help`", name)),
+        };
+        Ok(command)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Paused,
+    Running,
+    // run until the stack pointer drops back to sp, i.e. the call has returned
+    Next { sp: u8 },
+    // run until the stack pointer drops below sp, i.e. the current subroutine has returned
+    Finish { sp: u8 },
+    Draw,
+}
+
+pub struct Debugger {
+    pub breakpoints: BTreeSet<u16>,
+    pub watchpoints: Vec<Watchpoint>,
+    mode: Mode,
+    // set when resuming, so a breakpoint on the current instruction doesn't stop us straight away
+    resuming: bool,
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        Debugger::new()
+    }
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        Debugger {
+            breakpoints: BTreeSet::new(),
+            watchpoints: Vec::new(),
+            mode: Mode::Paused,
+            resuming: false,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.mode == Mode::Paused
+    }
+
+    // applies a command, returning the text to show the user
+    pub fn command(&mut self, command: Command, machine: &mut Machine) -> String {
+        match command {
+            Command::Break(addr) => {
+                self.breakpoints.insert(addr);
+                format!("breakpoint set at 0x{:04X}", addr)
+            }
+            Command::Delete(addr) => {
+                if self.breakpoints.remove(&addr) {
+                    format!("breakpoint at 0x{:04X} deleted", addr)
+                } else {
+                    format!("no breakpoint at 0x{:04X}", addr)
+                }
+            }
+            Command::Watch(watchpoint) => {
+                self.watchpoints.retain(|w| w.target != watchpoint.target);
+                self.watchpoints.push(watchpoint);
+                format!("watching {}", watchpoint.target)
+            }
+            Command::Unwatch(target) => {
+                self.watchpoints.retain(|w| w.target != target);
+                format!("no longer watching {}", target)
+            }
+            Command::Step => self.step(machine),
+            Command::Next => {
+                let instruction = peek_instruction(machine, machine.cpu.pc);
+                if matches!(instruction, Some(Instruction::Call(_))) {
+                    self.resume(Mode::Next { sp: machine.cpu.sp });
+                    String::new()
+                } else {
+                    self.step(machine)
+                }
+            }
+            Command::Finish => {
+                if machine.cpu.sp == 0 {
+                    "not inside a subroutine".to_string()
+                } else {
+                    self.resume(Mode::Finish { sp: machine.cpu.sp });
+                    String::new()
+                }
+            }
+            Command::Continue => {
+                self.resume(Mode::Running);
+                String::new()
+            }
+            Command::Pause => {
+                self.mode = Mode::Paused;
+                format!("paused at 0x{:04X}", machine.cpu.pc)
+            }
+            Command::Draw => {
+                self.resume(Mode::Draw);
+                String::new()
+            }
+            Command::Regs => machine.cpu.dump_state(),
+            Command::Examine { addr, len } => dump_memory(&machine.cpu.memory, addr, len),
+            Command::Info => self.info(),
+            Command::Help => HELP.to_string(),
+        }
+    }
+
+    // the debugger's replacement for Machine::run_frame, returns why execution stopped, if it did
+    pub fn run_frame(&mut self, machine: &mut Machine) -> Option<StopReason> {
+        if self.mode == Mode::Paused {
+            return None;
+        }
+
+        let mut drew = false;
+        machine.start_frame();
+        while machine.frame_has_time() {
+            if let Some(stop) = self.execute_instruction(machine, &mut drew) {
+                self.mode = Mode::Paused;
+                return Some(stop);
+            }
+        }
+        machine.cpu.decrement_timers();
+
+        if self.mode == Mode::Draw && drew {
+            self.mode = Mode::Paused;
+            return Some(StopReason::Frame);
+        }
+        None
+    }
+
+    fn resume(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.resuming = true;
+    }
+
+    fn step(&mut self, machine: &mut Machine) -> String {
+        self.mode = Mode::Paused;
+        self.resuming = true;
+        let stop = self
+            .execute_instruction(machine, &mut false)
+            .unwrap_or(StopReason::Step { pc: machine.cpu.pc });
+        stop.to_string()
+    }
+
+    fn execute_instruction(
+        &mut self,
+        machine: &mut Machine,
+        drew: &mut bool,
+    ) -> Option<StopReason> {
+        let pc = machine.cpu.pc;
+        if !std::mem::take(&mut self.resuming) && self.breakpoints.contains(&pc) {
+            return Some(StopReason::Breakpoint { pc });
+        }
+
+        let (reads, writes) =
+            peek_instruction(machine, pc).map_or((0, 0), Instruction::register_accesses);
+        let cpu = &mut machine.cpu;
+        let watching_memory = self
+            .watchpoints
+            .iter()
+            .any(|w| matches!(w.target, WatchTarget::Memory(_)));
+        if watching_memory {
+            cpu.memory_trace = Some(Vec::new());
+        }
+
+        // clear the draw flag so we can tell whether this instruction drew, then restore it for the frontend
+        let draw_flag = std::mem::take(&mut cpu.display.draw_flag);
+        let result = cpu.execute();
+        *drew |= cpu.display.draw_flag;
+        cpu.display.draw_flag |= draw_flag;
+        let trace = cpu.memory_trace.take().unwrap_or_default();
+
+        if let Err(err) = result {
+            return Some(StopReason::Error(err));
+        }
+
+        for watchpoint in &self.watchpoints {
+            let hit = match watchpoint.target {
+                WatchTarget::Memory(addr) => trace
+                    .iter()
+                    .find(|&&(a, access)| a == addr && watchpoint.kind.matches(access))
+                    .map(|&(_, access)| access),
+                WatchTarget::Register(reg) => [(writes, Access::Write), (reads, Access::Read)]
+                    .into_iter()
+                    .find(|&(mask, access)| {
+                        mask & (1 << reg) != 0 && watchpoint.kind.matches(access)
+                    })
+                    .map(|(_, access)| access),
+            };
+            if let Some(access) = hit {
+                return Some(StopReason::Watchpoint {
+                    target: watchpoint.target,
+                    access,
+                    pc,
+                });
+            }
+        }
+
+        match self.mode {
+            Mode::Next { sp } if cpu.sp <= sp => Some(StopReason::Step { pc: cpu.pc }),
+            Mode::Finish { sp } if cpu.sp < sp => Some(StopReason::Step { pc: cpu.pc }),
+            _ => None,
+        }
+    }
+
+    fn info(&self) -> String {
+        let mut lines = Vec::new();
+        for addr in &self.breakpoints {
+            lines.push(format!("breakpoint at 0x{:04X}", addr));
+        }
+        for watchpoint in &self.watchpoints {
+            let kind = match watchpoint.kind {
+                WatchKind::Read => "read",
+                WatchKind::Write => "write",
+                WatchKind::Access => "access",
+            };
+            lines.push(format!("{} watchpoint on {}", kind, watchpoint.target));
+        }
+        if lines.is_empty() {
+            "no breakpoints or watchpoints".to_string()
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+fn peek_instruction(machine: &Machine, pc: u16) -> Option<Instruction> {
+    let memory = &machine.cpu.memory;
+    let high = *memory.get(pc as usize)?;
+    let low = *memory.get(pc as usize + 1)?;
+    Instruction::decode((high as u16) << 8 | low as u16)
+}
+
+fn dump_memory(memory: &[u8], addr: usize, len: usize) -> String {
+    // both come straight from the user, so neither can be trusted to fit
+    let end = addr.saturating_add(len).min(memory.len());
+    if addr >= end {
+        return format!("0x{:04X} is outside memory", addr);
+    }
+    memory[addr..end]
+        .chunks(16)
+        .enumerate()
+        .map(|(row, bytes)| {
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            format!("0x{:04X}: {}", addr + row * 16, hex.join(" "))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+#[path = "./debugger_tests.rs"]
+mod debugger_tests;
@@ -0,0 +1,217 @@
+use super::*;
+use crate::{cpu::Platform, quirks::Quirks};
+
+fn build_machine(rom: &[u8]) -> Machine {
+    let mut machine = Machine::new(Platform::Chip8, Quirks::default());
+    machine.load_rom(rom);
+    machine
+}
+
+// 0x200: call 0x208, then spin
+// 0x208: V0 = 1, V1 = V0, return
+const CALL_ROM: [u8; 14] = [
+    0x22, 0x08, 0x12, 0x02, 0x00, 0x00, 0x00, 0x00, 0x60, 0x01, 0x81, 0x00, 0x00, 0xEE,
+];
+
+fn command(debugger: &mut Debugger, machine: &mut Machine, line: &str) -> String {
+    debugger.command(line.parse().unwrap(), machine)
+}
+
+#[test]
+fn test_starts_paused() {
+    let mut machine = build_machine(&CALL_ROM);
+    let mut debugger = Debugger::new();
+
+    assert!(debugger.is_paused());
+    assert_eq!(debugger.run_frame(&mut machine), None);
+    assert_eq!(machine.cpu.pc, 0x200);
+}
+
+#[test]
+fn test_breakpoint() {
+    let mut machine = build_machine(&CALL_ROM);
+    let mut debugger = Debugger::new();
+    command(&mut debugger, &mut machine, "break 20a");
+    command(&mut debugger, &mut machine, "c");
+
+    assert_eq!(
+        debugger.run_frame(&mut machine),
+        Some(StopReason::Breakpoint { pc: 0x20A })
+    );
+    assert!(debugger.is_paused());
+    assert_eq!(machine.cpu.v[0], 1);
+    assert_eq!(machine.cpu.v[1], 0);
+
+    // continuing doesn't stop on the breakpoint we're sitting on
+    command(&mut debugger, &mut machine, "c");
+    assert_eq!(debugger.run_frame(&mut machine), None);
+    assert_eq!(machine.cpu.v[1], 1);
+}
+
+#[test]
+fn test_step() {
+    let mut machine = build_machine(&CALL_ROM);
+    let mut debugger = Debugger::new();
+
+    assert_eq!(
+        command(&mut debugger, &mut machine, "s"),
+        "stopped at 0x0208"
+    );
+    assert_eq!(machine.cpu.sp, 1);
+    assert!(debugger.is_paused());
+}
+
+#[test]
+fn test_next_runs_over_calls() {
+    let mut machine = build_machine(&CALL_ROM);
+    let mut debugger = Debugger::new();
+    command(&mut debugger, &mut machine, "next");
+
+    assert_eq!(
+        debugger.run_frame(&mut machine),
+        Some(StopReason::Step { pc: 0x202 })
+    );
+    assert_eq!(machine.cpu.sp, 0);
+    assert_eq!(machine.cpu.v[1], 1);
+}
+
+#[test]
+fn test_finish() {
+    let mut machine = build_machine(&CALL_ROM);
+    let mut debugger = Debugger::new();
+
+    assert_eq!(
+        command(&mut debugger, &mut machine, "finish"),
+        "not inside a subroutine"
+    );
+
+    command(&mut debugger, &mut machine, "step");
+    command(&mut debugger, &mut machine, "finish");
+
+    assert_eq!(
+        debugger.run_frame(&mut machine),
+        Some(StopReason::Step { pc: 0x202 })
+    );
+}
+
+#[test]
+fn test_register_watchpoint() {
+    let mut machine = build_machine(&CALL_ROM);
+    let mut debugger = Debugger::new();
+    command(&mut debugger, &mut machine, "rwatch v0");
+    command(&mut debugger, &mut machine, "c");
+
+    // V0 = 1 writes V0, the read comes from V1 = V0
+    assert_eq!(
+        debugger.run_frame(&mut machine),
+        Some(StopReason::Watchpoint {
+            target: WatchTarget::Register(0),
+            access: Access::Read,
+            pc: 0x20A,
+        })
+    );
+}
+
+#[test]
+fn test_memory_watchpoint() {
+    // I = 0x300, V0 = 7, store V0, load V0
+    let mut machine = build_machine(&[0xA3, 0x00, 0x60, 0x07, 0xF0, 0x55, 0xA3, 0x00, 0xF0, 0x65]);
+    let mut debugger = Debugger::new();
+    command(&mut debugger, &mut machine, "watch 0x300");
+    command(&mut debugger, &mut machine, "c");
+
+    assert_eq!(
+        debugger.run_frame(&mut machine),
+        Some(StopReason::Watchpoint {
+            target: WatchTarget::Memory(0x300),
+            access: Access::Write,
+            pc: 0x204,
+        })
+    );
+
+    command(&mut debugger, &mut machine, "awatch 300");
+    command(&mut debugger, &mut machine, "c");
+
+    assert_eq!(
+        debugger.run_frame(&mut machine),
+        Some(StopReason::Watchpoint {
+            target: WatchTarget::Memory(0x300),
+            access: Access::Read,
+            pc: 0x208,
+        })
+    );
+}
+
+#[test]
+fn test_draw() {
+    // spin on 0x200 until it's patched to fall through into drawing the font's 0
+    let mut machine = build_machine(&[0x12, 0x00, 0xA0, 0x00, 0xD0, 0x05, 0x12, 0x02]);
+    machine.cpu.display.draw_flag = false;
+    let mut debugger = Debugger::new();
+    command(&mut debugger, &mut machine, "draw");
+
+    assert_eq!(debugger.run_frame(&mut machine), None);
+    assert!(!machine.cpu.display.draw_flag);
+
+    machine.cpu.memory[0x201] = 0x02;
+    assert_eq!(debugger.run_frame(&mut machine), Some(StopReason::Frame));
+    assert!(machine.cpu.display.draw_flag);
+}
+
+#[test]
+fn test_error_pauses() {
+    let mut machine = build_machine(&[0x00, 0xEE]);
+    let mut debugger = Debugger::new();
+    command(&mut debugger, &mut machine, "c");
+
+    assert_eq!(
+        debugger.run_frame(&mut machine),
+        Some(StopReason::Error(CpuError::StackUnderflow { pc: 0x200 }))
+    );
+    assert!(debugger.is_paused());
+}
+
+#[test]
+fn test_examine() {
+    let mut machine = build_machine(&CALL_ROM);
+    let mut debugger = Debugger::new();
+
+    assert_eq!(
+        command(&mut debugger, &mut machine, "x 200 4"),
+        "0x0200: 22 08 12 02"
+    );
+    // runs up to the end of memory instead of overflowing
+    assert_eq!(
+        command(&mut debugger, &mut machine, "x FFE FFFFFFFFFFFFFFFF"),
+        "0x0FFE: 00 00"
+    );
+    assert_eq!(
+        command(&mut debugger, &mut machine, "x 12345"),
+        "0x12345 is outside memory"
+    );
+}
+
+#[test]
+fn test_parse_commands() {
+    assert_eq!("b 0x2A0".parse(), Ok(Command::Break(0x2A0)));
+    assert_eq!(
+        "awatch VF".parse(),
+        Ok(Command::Watch(Watchpoint {
+            target: WatchTarget::Register(0xF),
+            kind: WatchKind::Access,
+        }))
+    );
+    assert_eq!(
+        "x 300".parse(),
+        Ok(Command::Examine {
+            addr: 0x300,
+            len: 0x10
+        })
+    );
+    assert!("break".parse::<Command>().is_err());
+    assert_eq!(
+        "break 0x12345".parse::<Command>(),
+        Err("address `0x12345` is past 0xFFFF".to_string())
+    );
+    assert!("jump 200".parse::<Command>().is_err());
+}
@@ -0,0 +1,120 @@
+use std::collections::BTreeMap;
+
+use crate::instruction::{Instruction, Syntax};
+
+// roms are loaded here on every platform
+pub const ROM_START: u16 = 0x200;
+
+// one decoded instruction, or data that doesn't decode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub addr: u16,
+    pub bytes: Vec<u8>,
+    pub instruction: Option<Instruction>,
+}
+
+// linear sweep over the rom, F000 NNNN is read as a single four byte instruction
+pub fn decode(rom: &[u8], origin: u16) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut offset = 0;
+    while offset < rom.len() {
+        let addr = origin.wrapping_add(offset as u16);
+        let Some(word) = rom.get(offset..offset + 2) else {
+            lines.push(Line {
+                addr,
+                bytes: rom[offset..].to_vec(),
+                instruction: None,
+            });
+            break;
+        };
+
+        let instruction = Instruction::decode((word[0] as u16) << 8 | word[1] as u16);
+        let len = match instruction {
+            // a trailing F000 without its address is left as data
+            Some(instruction) if offset + instruction.size() as usize <= rom.len() => {
+                instruction.size() as usize
+            }
+            _ => 2,
+        };
+        lines.push(Line {
+            addr,
+            bytes: rom[offset..offset + len].to_vec(),
+            instruction: instruction.filter(|instruction| instruction.size() as usize == len),
+        });
+        offset += len;
+    }
+    lines
+}
+
+// jump and call targets that land on a decoded line, calls are named sub_, jumps label_
+pub fn labels(lines: &[Line]) -> BTreeMap<u16, String> {
+    let mut labels = BTreeMap::new();
+    for line in lines {
+        let (target, prefix) = match line.instruction {
+            Some(Instruction::Call(nnn)) => (nnn, "sub"),
+            Some(Instruction::Jump(nnn) | Instruction::JumpOffset(nnn)) => (nnn, "label"),
+            _ => continue,
+        };
+        if !lines.iter().any(|line| line.addr == target) {
+            continue;
+        }
+        // a call name wins over a jump name for the same address
+        let name = format!("{}_{:04x}", prefix, target);
+        if prefix == "sub" || !labels.contains_key(&target) {
+            labels.insert(target, name);
+        }
+    }
+    labels
+}
+
+pub fn disassemble(rom: &[u8], origin: u16, syntax: Syntax) -> String {
+    let lines = decode(rom, origin);
+    let labels = labels(&lines);
+
+    let mut output = Vec::new();
+    for line in &lines {
+        if let Some(label) = labels.get(&line.addr) {
+            output.push(match syntax {
+                Syntax::Octo => format!(": {}", label),
+                Syntax::Cowgod => format!("{}:", label),
+            });
+        }
+
+        let text = match line.instruction {
+            Some(Instruction::LoadLongI) => {
+                let nnnn = (line.bytes[2] as u16) << 8 | line.bytes[3] as u16;
+                format!(
+                    "{} 0x{:04X}",
+                    Instruction::LoadLongI.format(syntax, &labels),
+                    nnnn
+                )
+            }
+            Some(instruction) => instruction.format(syntax, &labels),
+            None => data(&line.bytes, syntax),
+        };
+        let bytes: Vec<String> = line
+            .bytes
+            .iter()
+            .map(|byte| format!("{:02X}", byte))
+            .collect();
+        output.push(format!(
+            "0x{:04X}  {:<11}  {}",
+            line.addr,
+            bytes.join(" "),
+            text
+        ));
+    }
+    output.join("\n")
+}
+
+fn data(bytes: &[u8], syntax: Syntax) -> String {
+    let bytes: Vec<String> = bytes.iter().map(|byte| format!("0x{:02X}", byte)).collect();
+    match syntax {
+        Syntax::Octo => bytes.join(" "),
+        Syntax::Cowgod => format!("DB {}", bytes.join(", ")),
+    }
+}
+
+#[cfg(test)]
+#[path = "./disasm_tests.rs"]
+mod disasm_tests;
@@ -0,0 +1,63 @@
+use super::*;
+
+// 0x200: call 0x208, then spin
+// 0x208: V0 = 1, return
+const CALL_ROM: [u8; 12] = [
+    0x22, 0x08, 0x12, 0x02, 0x00, 0x00, 0x00, 0x00, 0x60, 0x01, 0x00, 0xEE,
+];
+
+#[test]
+fn test_decode_lines() {
+    let lines = decode(&[0xF0, 0x00, 0x12, 0x34, 0x00, 0x00, 0xAB], ROM_START);
+
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].instruction, Some(Instruction::LoadLongI));
+    assert_eq!(lines[0].bytes, vec![0xF0, 0x00, 0x12, 0x34]);
+    assert_eq!(lines[1].addr, 0x204);
+    assert_eq!(lines[1].instruction, None);
+    assert_eq!(lines[2].bytes, vec![0xAB]);
+}
+
+#[test]
+fn test_truncated_long_load() {
+    let lines = decode(&[0xF0, 0x00], ROM_START);
+
+    assert_eq!(lines[0].instruction, None);
+    assert_eq!(lines[0].bytes, vec![0xF0, 0x00]);
+}
+
+#[test]
+fn test_labels() {
+    let lines = decode(&CALL_ROM, ROM_START);
+    let labels = labels(&lines);
+
+    assert_eq!(labels.get(&0x208), Some(&"sub_0208".to_string()));
+    assert_eq!(labels.get(&0x202), Some(&"label_0202".to_string()));
+    assert_eq!(labels.len(), 2);
+}
+
+#[test]
+fn test_disassemble_octo() {
+    assert_eq!(
+        disassemble(&CALL_ROM, ROM_START, Syntax::Octo),
+        "\
+0x0200  22 08        :call sub_0208
+: label_0202
+0x0202  12 02        jump label_0202
+0x0204  00 00        0x00 0x00
+0x0206  00 00        0x00 0x00
+: sub_0208
+0x0208  60 01        v0 := 0x01
+0x020A  00 EE        return"
+    );
+}
+
+#[test]
+fn test_disassemble_cowgod() {
+    assert_eq!(
+        disassemble(&[0xF0, 0x00, 0x12, 0x34, 0x00], ROM_START, Syntax::Cowgod),
+        "\
+0x0200  F0 00 12 34  LD I, LONG 0x1234
+0x0204  00           DB 0x00"
+    );
+}
@@ -1,29 +1,120 @@
-pub const WIDTH: usize = 64;
-pub const HEIGHT: usize = 32;
+pub const LORES_WIDTH: usize = 64;
+pub const LORES_HEIGHT: usize = 32;
+pub const HIRES_WIDTH: usize = 128;
+pub const HIRES_HEIGHT: usize = 64;
+
+// the framebuffer is sized for hi-res mode, lo-res mode only uses the top-left corner
+pub const WIDTH: usize = HIRES_WIDTH;
+pub const HEIGHT: usize = HIRES_HEIGHT;
+
+// each framebuffer cell holds one bit per XO-CHIP bitplane
+pub const PLANE_MASK: u8 = 0b11;
 
 pub struct Display {
     pub memory: [[u8; WIDTH]; HEIGHT],
+    pub hires: bool,
+    // bitplanes affected by drawing, clearing and scrolling
+    pub planes: u8,
     pub draw_flag: bool,
 }
 
+impl Default for Display {
+    fn default() -> Display {
+        Display::new()
+    }
+}
+
 impl Display {
     pub fn new() -> Display {
         Display {
             memory: [[0; WIDTH]; HEIGHT],
+            hires: false,
+            planes: 1,
             draw_flag: false,
         }
     }
 
-    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
-        self.memory[y][x] = on as u8;
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_WIDTH
+        } else {
+            LORES_WIDTH
+        }
     }
 
-    pub fn get_pixel(&mut self, x: usize, y: usize) -> bool {
-        self.memory[y][x] == 1
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            LORES_HEIGHT
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, plane: u8, on: bool) {
+        if on {
+            self.memory[y][x] |= plane;
+        } else {
+            self.memory[y][x] &= !plane;
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize, plane: u8) -> bool {
+        self.memory[y][x] & plane != 0
     }
 
     pub fn cls(&mut self) {
         self.memory = [[0; WIDTH]; HEIGHT];
         self.draw_flag = true;
     }
+
+    pub fn clear_planes(&mut self) {
+        let keep = !self.planes & PLANE_MASK;
+        for row in self.memory.iter_mut() {
+            for pixel in row.iter_mut() {
+                *pixel &= keep;
+            }
+        }
+        self.draw_flag = true;
+    }
+
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        self.cls();
+    }
+
+    pub fn scroll_down(&mut self, n: usize) {
+        self.scroll(0, n as isize);
+    }
+
+    pub fn scroll_up(&mut self, n: usize) {
+        self.scroll(0, -(n as isize));
+    }
+
+    pub fn scroll_right(&mut self, n: usize) {
+        self.scroll(n as isize, 0);
+    }
+
+    pub fn scroll_left(&mut self, n: usize) {
+        self.scroll(-(n as isize), 0);
+    }
+
+    // moves the selected planes by (dx, dy), pixels scrolled in from the edge are blank
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let width = self.width() as isize;
+        let height = self.height() as isize;
+        let old = self.memory;
+        for y in 0..height {
+            for x in 0..width {
+                let (src_x, src_y) = (x - dx, y - dy);
+                let moved = if (0..width).contains(&src_x) && (0..height).contains(&src_y) {
+                    old[src_y as usize][src_x as usize]
+                } else {
+                    0
+                };
+                let pixel = &mut self.memory[y as usize][x as usize];
+                *pixel = (*pixel & !self.planes) | (moved & self.planes);
+            }
+        }
+        self.draw_flag = true;
+    }
 }
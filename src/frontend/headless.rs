@@ -0,0 +1,62 @@
+use std::io::Write;
+
+use marisa_rs::{
+    headless,
+    persistence::Blender,
+    screenshot::{self, ImageFormat},
+};
+
+use crate::RunArgs;
+
+// runs the ROM for a fixed number of frames without touching SDL, then dumps
+// the cpu state; a crash still writes the screenshot and exits with an error
+pub fn run(args: &RunArgs, rom_data: &[u8]) -> Result<(), anyhow::Error> {
+    let events = match &args.keys {
+        Some(path) => headless::parse_keys(&std::fs::read_to_string(path)?)
+            .map_err(|err| anyhow::anyhow!("{}: {}", path.display(), err))?,
+        None => Vec::new(),
+    };
+    // checked up front, so a typo doesn't cost a whole run
+    let screenshot = match &args.screenshot {
+        Some(path) if path.as_os_str() == "-" => Some((path, ImageFormat::Ascii)),
+        Some(path) => Some((
+            path,
+            ImageFormat::from_path(path)
+                .map_err(|err| anyhow::anyhow!("{}: {}", path.display(), err))?,
+        )),
+        None => None,
+    };
+
+    let (mut machine, _) = args.machine(rom_data);
+    // fed every frame so the screenshot is smoothed the same way the window would be
+    let mut blender = Blender::new(args.persistence);
+    let result = headless::run_with(&mut machine, args.frames, &events, |machine| {
+        blender.update(machine.framebuffer())
+    });
+    // the frame that crashed didn't finish, but what it drew is still worth seeing
+    if result.is_err() {
+        blender.update(machine.framebuffer());
+    }
+
+    if let Some((path, format)) = screenshot {
+        let image = screenshot::encode_shaded(blender.shades(), format);
+        if path.as_os_str() == "-" {
+            std::io::stdout().write_all(&image)?;
+        } else {
+            std::fs::write(path, image)?;
+        }
+    }
+
+    match result {
+        Ok(frames) => {
+            if machine.cpu.exited {
+                args.status(format_args!("ROM exited after {} frames", frames));
+            } else {
+                args.status(format_args!("Ran {} frames", frames));
+            }
+            args.status(format_args!("{}", machine.cpu.dump_state()));
+            Ok(())
+        }
+        Err(err) => Err(anyhow::anyhow!("{}\n{}", err, machine.cpu.dump_state())),
+    }
+}
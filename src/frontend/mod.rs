@@ -0,0 +1,3 @@
+pub mod headless;
+#[cfg(feature = "sdl")]
+pub mod sdl;
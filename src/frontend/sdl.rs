@@ -0,0 +1,612 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::BufRead,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+    time::{Duration, Instant},
+};
+
+use marisa_rs::{
+    audio::Beeper,
+    debugger::{Command, Debugger},
+    display,
+    keymap::{axis_direction, HostKey, Keymap},
+    movie::{Movie, Player, Recorder},
+    palette::Palette,
+    persistence::{Blender, Persistence},
+    render,
+    rewind::Rewind,
+    scheduler::{present_wait, Scheduler},
+    Machine, TIMER_FREQUENCY,
+};
+use sdl3::{
+    audio::{AudioCallback, AudioFormat, AudioSpec, AudioStream},
+    event::Event,
+    gamepad::{Axis, Button, Gamepad},
+    keyboard::{Keycode, Mod, Scancode},
+    pixels::{Color, PixelFormat},
+    rect::Rect,
+    render::ScaleMode,
+    sys::pixels::SDL_PixelFormat,
+    video::Window,
+    GamepadSubsystem,
+};
+
+use crate::RunArgs;
+
+const WINDOW_TITLE: &str = "marisa-rs v2025.4.0-alpha";
+// starting window size, it can be resized from there
+const SCALE: u32 = 8;
+const WINDOW_WIDTH: u32 = (display::WIDTH as u32) * SCALE;
+const WINDOW_HEIGHT: u32 = (display::HEIGHT as u32) * SCALE;
+
+const AUDIO_SAMPLE_RATE: u32 = 48000;
+
+struct SdlBeeper(Beeper);
+
+impl AudioCallback<f32> for SdlBeeper {
+    fn callback(&mut self, stream: &mut AudioStream, requested: i32) {
+        let mut samples = vec![0.0; requested.max(0) as usize];
+        self.0.fill(&mut samples);
+        if let Err(err) = stream.put_data_f32(&samples) {
+            eprintln!("Failed to queue audio: {}", err);
+        }
+    }
+}
+
+// reads debugger commands on a separate thread, so the window stays responsive
+fn spawn_stdin_reader() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+// movies need every frame to run exactly as recorded, so rewinding and loading states are off
+enum Session {
+    Live,
+    Recording(Recorder, PathBuf),
+    Playing(Player),
+}
+
+impl Session {
+    fn is_live(&self) -> bool {
+        matches!(self, Session::Live)
+    }
+
+    // writes out a recording, called however the emulator stops
+    fn finish(self, machine: &Machine) -> Result<(), anyhow::Error> {
+        if let Session::Recording(recorder, path) = self {
+            let movie = recorder.finish(machine);
+            std::fs::write(&path, movie.to_string())?;
+            println!("Recorded {} frames to {}", movie.frames, path.display());
+        }
+        Ok(())
+    }
+}
+
+// the keymap's names resolved to SDL keys and gamepad controls
+struct KeyBindings {
+    keycodes: HashMap<Keycode, Vec<usize>>,
+    scancodes: HashMap<Scancode, Vec<usize>>,
+    buttons: HashMap<Button, Vec<usize>>,
+    // an axis and the direction it's pushed in
+    axes: HashMap<(Axis, char), Vec<usize>>,
+    deadzone: f32,
+}
+
+impl KeyBindings {
+    fn new(keymap: &Keymap) -> Result<KeyBindings, anyhow::Error> {
+        let mut bindings = KeyBindings {
+            keycodes: HashMap::new(),
+            scancodes: HashMap::new(),
+            buttons: HashMap::new(),
+            axes: HashMap::new(),
+            deadzone: keymap.deadzone,
+        };
+        for (host_key, keys) in keymap.bindings() {
+            let keys = keys.iter().map(|&key| key as usize);
+            match host_key {
+                HostKey::Keycode(name) => {
+                    let keycode = Keycode::from_name(name)
+                        .ok_or_else(|| anyhow::anyhow!("unknown keycode `{}`", name))?;
+                    bindings.keycodes.entry(keycode).or_default().extend(keys);
+                }
+                HostKey::Scancode(name) => {
+                    let scancode = Scancode::from_name(name)
+                        .ok_or_else(|| anyhow::anyhow!("unknown scancode `{}`", name))?;
+                    bindings.scancodes.entry(scancode).or_default().extend(keys);
+                }
+                HostKey::Gamepad(name) => {
+                    let unknown = || anyhow::anyhow!("unknown gamepad button or axis `{}`", name);
+                    let direction = name.chars().last().filter(|&end| end == '+' || end == '-');
+                    if let Some(direction) = direction {
+                        let axis =
+                            Axis::from_string(&name[..name.len() - 1]).ok_or_else(unknown)?;
+                        bindings
+                            .axes
+                            .entry((axis, direction))
+                            .or_default()
+                            .extend(keys);
+                    } else {
+                        let button = Button::from_string(name).ok_or_else(unknown)?;
+                        bindings.buttons.entry(button).or_default().extend(keys);
+                    }
+                }
+            }
+        }
+        Ok(bindings)
+    }
+
+    fn keys(&self, keycode: Option<Keycode>, scancode: Option<Scancode>) -> Vec<usize> {
+        let keycode_keys = keycode.and_then(|keycode| self.keycodes.get(&keycode));
+        let scancode_keys = scancode.and_then(|scancode| self.scancodes.get(&scancode));
+        keycode_keys
+            .into_iter()
+            .chain(scancode_keys)
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
+// gamepads are opened as they're plugged in, SDL also reports the ones
+// connected at startup that way
+struct Gamepads {
+    subsystem: GamepadSubsystem,
+    open: HashMap<u32, Gamepad>,
+    // buttons held down on each gamepad, so unplugging one releases only its keys
+    buttons: HashSet<(u32, Button)>,
+    // where each stick or trigger is pushed, so only crossing the deadzone presses keys
+    axes: HashMap<(u32, Axis), char>,
+}
+
+impl Gamepads {
+    // hex key presses and releases caused by a gamepad event
+    fn handle(&mut self, event: &Event, bindings: &KeyBindings) -> Vec<(usize, bool)> {
+        let mut changes = Vec::new();
+        match *event {
+            Event::ControllerDeviceAdded { which, .. } => match self.subsystem.open(which) {
+                Ok(gamepad) => {
+                    let name = gamepad.name().unwrap_or_else(|| "unknown".to_string());
+                    println!("Gamepad connected: {}", name);
+                    self.open.insert(which, gamepad);
+                }
+                Err(err) => eprintln!("Failed to open gamepad: {}", err),
+            },
+            Event::ControllerDeviceRemoved { which, .. } => {
+                if self.open.remove(&which).is_some() {
+                    println!("Gamepad disconnected");
+                }
+                // whatever it was holding won't get a release event
+                self.buttons.retain(|&(id, button)| {
+                    if id == which {
+                        for &key in bindings.buttons.get(&button).into_iter().flatten() {
+                            changes.push((key, false));
+                        }
+                    }
+                    id != which
+                });
+                self.axes.retain(|&(id, axis), &mut direction| {
+                    if id == which {
+                        for &key in bindings.axes.get(&(axis, direction)).into_iter().flatten() {
+                            changes.push((key, false));
+                        }
+                    }
+                    id != which
+                });
+            }
+            Event::ControllerButtonDown { which, button, .. }
+            | Event::ControllerButtonUp { which, button, .. } => {
+                let pressed = matches!(event, Event::ControllerButtonDown { .. });
+                if pressed {
+                    self.buttons.insert((which, button));
+                } else {
+                    self.buttons.remove(&(which, button));
+                }
+                for &key in bindings.buttons.get(&button).into_iter().flatten() {
+                    changes.push((key, pressed));
+                }
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                let direction = axis_direction(value, bindings.deadzone);
+                let previous = match direction {
+                    Some(direction) => self.axes.insert((which, axis), direction),
+                    None => self.axes.remove(&(which, axis)),
+                };
+                if previous != direction {
+                    if let Some(previous) = previous {
+                        for &key in bindings.axes.get(&(axis, previous)).into_iter().flatten() {
+                            changes.push((key, false));
+                        }
+                    }
+                    if let Some(direction) = direction {
+                        for &key in bindings.axes.get(&(axis, direction)).into_iter().flatten() {
+                            changes.push((key, true));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        changes
+    }
+}
+
+fn handle_key_event(key: usize, pressed: bool, machine: &mut Machine, session: &mut Session) {
+    match session {
+        Session::Live => machine.set_key(key, pressed),
+        Session::Recording(recorder, _) => recorder.set_key(machine, key, pressed),
+        // the movie has the keypad
+        Session::Playing(_) => {}
+    }
+}
+
+// F1-F9 pick a save state slot
+fn state_slot(keycode: Keycode) -> Option<u8> {
+    match keycode {
+        Keycode::F1 => Some(1),
+        Keycode::F2 => Some(2),
+        Keycode::F3 => Some(3),
+        Keycode::F4 => Some(4),
+        Keycode::F5 => Some(5),
+        Keycode::F6 => Some(6),
+        Keycode::F7 => Some(7),
+        Keycode::F8 => Some(8),
+        Keycode::F9 => Some(9),
+        _ => None,
+    }
+}
+
+// one refresh of the display the window is on, 60 Hz when it doesn't say
+fn refresh_period(window: &Window) -> Duration {
+    let rate = window
+        .get_display()
+        .and_then(|display| display.get_mode())
+        .map(|mode| mode.refresh_rate)
+        .unwrap_or(0.0);
+    let rate = if rate > 0.0 {
+        rate
+    } else {
+        TIMER_FREQUENCY as f32
+    };
+    Duration::from_secs_f32(1.0 / rate)
+}
+
+// slots live next to the ROM, e.g. game.ch8 -> game.state1
+fn state_path(rom_path: &Path, slot: u8) -> PathBuf {
+    rom_path.with_extension(format!("state{}", slot))
+}
+
+// failures are reported but never stop the emulator
+fn handle_state_hotkey(slot: u8, save: bool, rom_path: &Path, machine: &mut Machine) {
+    let path = state_path(rom_path, slot);
+    if save {
+        match std::fs::write(&path, machine.save_state()) {
+            Ok(()) => println!("Saved slot {} to {}", slot, path.display()),
+            Err(err) => eprintln!("Failed to save slot {}: {}", slot, err),
+        }
+    } else {
+        let result = std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok(machine.load_state(&data)?));
+        match result {
+            Ok(()) => println!("Loaded slot {} from {}", slot, path.display()),
+            Err(err) => eprintln!("Failed to load slot {}: {}", slot, err),
+        }
+    }
+}
+
+pub fn run(
+    args: &RunArgs,
+    rom_path: &Path,
+    rom_data: &[u8],
+    keymap: &Keymap,
+    mut palette: Palette,
+) -> Result<(), anyhow::Error> {
+    // checked before the window opens, so a typo in the config fails fast
+    let bindings = KeyBindings::new(keymap)?;
+
+    let sdl_context = sdl3::init()?;
+    let video_subsystem = sdl_context.video()?;
+
+    let mut window = video_subsystem.window(WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT);
+    window.position_centered().resizable();
+    if args.fullscreen {
+        window.fullscreen();
+    }
+    // presents at the monitor's refresh rate, emulation is paced separately
+    sdl3::hint::set(sdl3::hint::names::RENDER_VSYNC, "1");
+    let mut canvas = window.build()?.into_canvas();
+    let mut fullscreen = args.fullscreen;
+
+    // the framebuffer is drawn into one texture and scaled up in a single copy
+    let texture_creator = canvas.texture_creator();
+    let mut texture = texture_creator.create_texture_streaming(
+        PixelFormat::try_from(SDL_PixelFormat::RGBA32)?,
+        display::WIDTH as u32,
+        display::HEIGHT as u32,
+    )?;
+    texture.set_scale_mode(ScaleMode::Nearest);
+    let mut frame = vec![0; render::FRAME_SIZE];
+    let mut blender = Blender::new(args.persistence);
+
+    // a missing audio device shouldn't stop the game from running
+    let beeper = SdlBeeper(Beeper::new(
+        AUDIO_SAMPLE_RATE,
+        args.beep_frequency,
+        args.beep_volume,
+        args.beep_waveform,
+    ));
+    let spec = AudioSpec::new(
+        Some(AUDIO_SAMPLE_RATE as i32),
+        Some(1),
+        Some(AudioFormat::f32_sys()),
+    );
+    let mut audio_stream = match sdl_context
+        .audio()
+        .and_then(|audio| audio.open_playback_stream(&spec, beeper))
+    {
+        Ok(stream) => {
+            stream.resume()?;
+            Some(stream)
+        }
+        Err(err) => {
+            eprintln!("Audio disabled: {}", err);
+            None
+        }
+    };
+
+    let (mut machine, mut session) = if let Some(movie_path) = &args.play {
+        let movie: Movie = std::fs::read_to_string(movie_path)?
+            .parse()
+            .map_err(|err| anyhow::anyhow!("{}: {}", movie_path.display(), err))?;
+        println!(
+            "Playing {} frames from {}",
+            movie.frames,
+            movie_path.display()
+        );
+        (
+            movie.machine(rom_data)?,
+            Session::Playing(Player::new(movie)),
+        )
+    } else {
+        let (machine, seed) = args.machine(rom_data);
+
+        let session = match &args.record {
+            Some(path) => {
+                Session::Recording(Recorder::new(&machine, seed, args.profile()), path.clone())
+            }
+            None => Session::Live,
+        };
+        (machine, session)
+    };
+
+    let mut event_pump = sdl_context.event_pump()?;
+
+    // like audio, missing gamepad support only disables gamepads
+    let mut gamepads = match sdl_context.gamepad() {
+        Ok(subsystem) => Some(Gamepads {
+            subsystem,
+            open: HashMap::new(),
+            buttons: HashSet::new(),
+            axes: HashMap::new(),
+        }),
+        Err(err) => {
+            eprintln!("Gamepads disabled: {}", err);
+            None
+        }
+    };
+
+    let mut debugger = if args.debug {
+        println!("Debugger ready, type `help` for a list of commands");
+        Some((Debugger::new(), spawn_stdin_reader()))
+    } else {
+        None
+    };
+
+    let mut rewind = Rewind::new(
+        args.rewind_interval,
+        (args.rewind_seconds * TIMER_FREQUENCY / args.rewind_interval.max(1)) as usize,
+    );
+    let mut rewinding = false;
+    let mut scheduler = Scheduler::new(Instant::now());
+    let mut last_present = Instant::now();
+    // shown in the title bar while the ROM is stuck on FX0A
+    let mut waiting_for_key = false;
+
+    'running: loop {
+        while let Some(event) = event_pump.poll_event() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => break 'running,
+                Event::KeyDown {
+                    keycode: Some(Keycode::F11),
+                    repeat: false,
+                    ..
+                } => {
+                    fullscreen = !fullscreen;
+                    if let Err(err) = canvas.window_mut().set_fullscreen(fullscreen) {
+                        eprintln!("Failed to toggle fullscreen: {}", err);
+                    }
+                }
+                Event::KeyDown {
+                    keycode: Some(Keycode::F10),
+                    repeat: false,
+                    ..
+                } => {
+                    palette = palette.next();
+                    println!("Palette: {}", palette);
+                    machine.cpu.display.draw_flag = true;
+                }
+                // shift + F1-F9 saves, F1-F9 alone loads
+                Event::KeyDown {
+                    keycode: Some(key),
+                    keymod,
+                    repeat: false,
+                    ..
+                } if state_slot(key).is_some() => {
+                    let save = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+                    if save || session.is_live() {
+                        handle_state_hotkey(state_slot(key).unwrap(), save, rom_path, &mut machine);
+                    } else {
+                        println!("Loading states is disabled during movies");
+                    }
+                }
+                // hold backspace to rewind
+                Event::KeyDown {
+                    keycode: Some(Keycode::Backspace),
+                    repeat: false,
+                    ..
+                } => {
+                    if session.is_live() {
+                        rewinding = true;
+                    } else {
+                        println!("Rewind is disabled during movies");
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(Keycode::Backspace),
+                    ..
+                } => rewinding = false,
+                Event::KeyUp {
+                    keycode, scancode, ..
+                } => {
+                    for key in bindings.keys(keycode, scancode) {
+                        handle_key_event(key, false, &mut machine, &mut session);
+                    }
+                }
+                Event::KeyDown {
+                    keycode, scancode, ..
+                } => {
+                    for key in bindings.keys(keycode, scancode) {
+                        handle_key_event(key, true, &mut machine, &mut session);
+                    }
+                }
+                event => {
+                    if let Some(gamepads) = gamepads.as_mut() {
+                        for (key, pressed) in gamepads.handle(&event, &bindings) {
+                            handle_key_event(key, pressed, &mut machine, &mut session);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some((debugger, commands)) = debugger.as_mut() {
+            while let Ok(line) = commands.try_recv() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match line.parse::<Command>() {
+                    Ok(command) => {
+                        let output = debugger.command(command, &mut machine);
+                        if !output.is_empty() {
+                            println!("{}", output);
+                        }
+                    }
+                    Err(err) => eprintln!("{}", err),
+                }
+            }
+        }
+
+        // every frame that's come due since the last pass, more than one after a stall
+        let frames = scheduler.frames_due(Instant::now());
+        for _ in 0..frames {
+            if rewinding {
+                rewind.rewind(&mut machine);
+            } else if let Some((debugger, _)) = debugger.as_mut() {
+                if !debugger.is_paused() {
+                    rewind.record(&machine);
+                }
+                if let Some(stop) = debugger.run_frame(&mut machine) {
+                    println!("{}", stop);
+                }
+            } else {
+                rewind.record(&machine);
+                if let Session::Playing(player) = &mut session {
+                    player.apply(&mut machine);
+                }
+                if let Err(err) = machine.run_frame() {
+                    // a recording that ends in a crash is the one worth keeping
+                    session.finish(&machine)?;
+                    return Err(anyhow::anyhow!("{}\n{}", err, machine.cpu.dump_state()));
+                }
+                match &mut session {
+                    Session::Live => {}
+                    Session::Recording(recorder, _) => recorder.end_frame(),
+                    Session::Playing(player) => {
+                        player.end_frame();
+                        if player.is_finished() {
+                            match player.verify(&machine) {
+                                Ok(()) => println!("Movie finished, final state matches"),
+                                Err(err) => eprintln!("Movie finished: {}", err),
+                            }
+                            session = Session::Live;
+                        }
+                    }
+                }
+            }
+
+            if machine.cpu.exited {
+                println!("ROM exited\n{}", machine.cpu.dump_state());
+                break 'running;
+            }
+            // smoothing changes the picture between draws too, so it's fed every frame
+            blender.update(machine.framebuffer());
+        }
+
+        if machine.waiting_for_key() != waiting_for_key {
+            waiting_for_key = !waiting_for_key;
+            let title = if waiting_for_key {
+                format!("{} (waiting for input)", WINDOW_TITLE)
+            } else {
+                WINDOW_TITLE.to_string()
+            };
+            canvas.window_mut().set_title(&title)?;
+        }
+
+        let paused = debugger
+            .as_ref()
+            .is_some_and(|(debugger, _)| debugger.is_paused());
+        if let Some(mut beeper) = audio_stream.as_mut().and_then(|stream| stream.lock()) {
+            beeper.0.set_pattern(machine.audio_pattern());
+            beeper
+                .0
+                .set_active(machine.sound_active() && !paused && !rewinding);
+        }
+
+        if frames > 0 && (machine.framebuffer().draw_flag || blender.mode() != Persistence::Off) {
+            render::rgba_shaded(blender.shades(), &palette.colors, &mut frame);
+            texture.update(None, &frame, display::WIDTH * 4)?;
+            machine.cpu.display.draw_flag = false;
+        }
+        // presented every frame, the window may have been resized or uncovered
+        let (x, y, width, height) = render::letterbox(canvas.output_size()?, args.scaling);
+        let [r, g, b] = palette.colors[0];
+        canvas.set_draw_color(Color::RGB(r, g, b));
+        canvas.clear();
+        canvas.copy(&texture, None, Rect::new(x, y, width, height))?;
+        canvas.present();
+
+        // the vsync hint is only a request the driver may ignore
+        std::thread::sleep(present_wait(
+            last_present.elapsed(),
+            refresh_period(canvas.window()),
+        ));
+        last_present = Instant::now();
+    }
+
+    session.finish(&machine)
+}
@@ -0,0 +1,157 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    cpu::CpuError, headless, movie::InputEvent, quirks::Profile, rng::RngMode, screenshot, Machine,
+};
+
+const HEADER: &str = "marisa-golden 1";
+
+// the expected screen of a ROM after a number of frames, plus how to get there.
+// stored as text next to the ROM, so changes show up in review:
+//   marisa-golden 1
+//   frames 120
+//   profile vip
+//   key 10 5 down
+//   screen
+//   ##..##..
+//   ...
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Golden {
+    pub frames: u64,
+    pub profile: Profile,
+    pub keys: Vec<InputEvent>,
+    // screenshot::ascii of the framebuffer
+    pub screen: String,
+}
+
+impl Golden {
+    // runs the ROM the way this golden describes and returns the screen it ends on
+    pub fn capture(&self, rom: &[u8]) -> Result<String, CpuError> {
+        let mut machine = Machine::new(self.profile.platform(), self.profile.quirks());
+        // CXKK has to be reproducible for the screens to match
+        machine.cpu.rng = RngMode::Seeded.create(0);
+        machine.load_rom(rom);
+        headless::run(&mut machine, self.frames, &self.keys)?;
+        Ok(screenshot::ascii(machine.framebuffer()))
+    }
+
+    // None when the ROM ends on the expected screen, a visual diff otherwise
+    pub fn check(&self, rom: &[u8]) -> Result<Option<String>, CpuError> {
+        Ok(diff(&self.screen, &self.capture(rom)?))
+    }
+}
+
+impl fmt::Display for Golden {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "frames {}", self.frames)?;
+        writeln!(f, "profile {}", self.profile)?;
+        for event in &self.keys {
+            let action = if event.pressed { "down" } else { "up" };
+            writeln!(f, "key {} {:x} {}", event.frame, event.key, action)?;
+        }
+        writeln!(f, "screen")?;
+        write!(f, "{}", self.screen)
+    }
+}
+
+impl FromStr for Golden {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Golden, String> {
+        let mut lines = s.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err("not a golden file".to_string());
+        }
+
+        let (mut frames, mut profile) = (None, None);
+        let mut keys = Vec::new();
+        let mut has_screen = false;
+        for (index, line) in lines.by_ref() {
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => {}
+                ["frames", value] => {
+                    frames = Some(value.parse().map_err(|_| error("invalid frames".into()))?)
+                }
+                ["profile", name] => profile = Some(name.parse::<Profile>().map_err(error)?),
+                ["key", event @ ..] => {
+                    keys.push(headless::parse_event(event, &keys).map_err(error)?)
+                }
+                ["screen"] => {
+                    has_screen = true;
+                    break;
+                }
+                _ => return Err(error(format!("unrecognized line `{}`", line.trim()))),
+            }
+        }
+
+        let missing = |field: &str| format!("missing `{}`", field);
+        if !has_screen {
+            return Err(missing("screen"));
+        }
+        let mut rows = String::new();
+        for (_, line) in lines {
+            rows.push_str(line.trim_end());
+            rows.push('\n');
+        }
+
+        Ok(Golden {
+            frames: frames.ok_or_else(|| missing("frames"))?,
+            profile: profile.ok_or_else(|| missing("profile"))?,
+            keys,
+            screen: rows,
+        })
+    }
+}
+
+// expected and actual screens one above the other, then a map of the pixels
+// that differ: `-` should be lit but isn't, `+` shouldn't be lit but is, `~`
+// is lit on the wrong planes
+pub fn diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+
+    let size = |screen: &str| {
+        let width = screen.lines().next().map_or(0, |row| row.chars().count());
+        (width, screen.lines().count())
+    };
+    let (expected_size, actual_size) = (size(expected), size(actual));
+
+    let mut out = String::new();
+    if expected_size != actual_size {
+        out.push_str(&format!(
+            "expected a {}x{} screen, got {}x{}\n",
+            expected_size.0, expected_size.1, actual_size.0, actual_size.1
+        ));
+    }
+    out.push_str("expected:\n");
+    out.push_str(expected);
+    out.push_str("actual:\n");
+    out.push_str(actual);
+
+    if expected_size == actual_size {
+        let mut changed = 0;
+        out.push_str("diff:\n");
+        for (expected_row, actual_row) in expected.lines().zip(actual.lines()) {
+            for (want, got) in expected_row.chars().zip(actual_row.chars()) {
+                out.push(match (want, got) {
+                    _ if want == got => '.',
+                    (_, '.') => '-',
+                    ('.', _) => '+',
+                    _ => '~',
+                });
+                changed += (want != got) as usize;
+            }
+            out.push('\n');
+        }
+        out.push_str(&format!("{} pixels differ\n", changed));
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+#[path = "./golden_tests.rs"]
+mod golden_tests;
@@ -0,0 +1,93 @@
+use super::*;
+use crate::assembler::assemble;
+
+// draws a 0 in the corner, then a 5 next to it once key 5 goes down
+fn rom() -> Vec<u8> {
+    assemble(
+        "
+        v1 := 5
+        i := hex v0
+        sprite v0 v0 5
+        loop
+            if v1 -key then
+        again
+        v2 := 5
+        i := hex v2
+        sprite v2 v0 5
+        loop again
+        ",
+    )
+    .unwrap()
+    .rom
+}
+
+fn golden(keys: &str) -> Golden {
+    format!("marisa-golden 1\nframes 10\nprofile vip\n{}screen\n", keys)
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_round_trip() {
+    let mut golden = golden("key 2 5 down\nkey 4 5 up\n");
+    golden.screen = golden.capture(&rom()).unwrap();
+
+    assert_eq!(golden.keys.len(), 2);
+    assert_eq!(golden.to_string().parse::<Golden>(), Ok(golden));
+}
+
+#[test]
+fn test_capture() {
+    let screen = golden("").capture(&rom()).unwrap();
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines.len(), 32);
+    assert!(lines[0].starts_with("####....."));
+
+    let screen = golden("key 2 5 down\n").capture(&rom()).unwrap();
+    assert!(screen.lines().next().unwrap().starts_with("####.####."));
+}
+
+#[test]
+fn test_check() {
+    let mut golden = golden("key 2 5 down\n");
+    golden.screen = golden.capture(&rom()).unwrap();
+    assert_eq!(golden.check(&rom()), Ok(None));
+
+    golden.keys.clear();
+    let diff = golden.check(&rom()).unwrap().unwrap();
+    assert!(diff.contains("\ndiff:\n"));
+    assert!(diff.ends_with("14 pixels differ\n"));
+}
+
+#[test]
+fn test_diff() {
+    assert_eq!(diff("#.\n", "#.\n"), None);
+    assert_eq!(
+        diff("#.#+\n", ".##*\n"),
+        Some("expected:\n#.#+\nactual:\n.##*\ndiff:\n-+.~\n3 pixels differ\n".to_string())
+    );
+    assert_eq!(
+        diff("#.\n", "#...\n"),
+        Some("expected a 2x1 screen, got 4x1\nexpected:\n#.\nactual:\n#...\n".to_string())
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        "frames 10".parse::<Golden>(),
+        Err("not a golden file".to_string())
+    );
+    assert_eq!(
+        "marisa-golden 1\nframes 10\nprofile vip\n".parse::<Golden>(),
+        Err("missing `screen`".to_string())
+    );
+    assert_eq!(
+        "marisa-golden 1\nframes 10\nkey 1 g down\nscreen\n".parse::<Golden>(),
+        Err("line 3: invalid key `g`".to_string())
+    );
+    assert_eq!(
+        "marisa-golden 1\nframes 10\nspeed 3\nscreen\n".parse::<Golden>(),
+        Err("line 3: unrecognized line `speed 3`".to_string())
+    );
+}
@@ -0,0 +1,82 @@
+use crate::{cpu::CpuError, movie::InputEvent, Machine};
+
+// a key script has one keypad transition per line, applied before that frame runs:
+//   # hold 5 for a second
+//   0 5 down
+//   60 5 up
+pub fn parse_keys(script: &str) -> Result<Vec<InputEvent>, String> {
+    let mut events = Vec::new();
+    for (index, line) in script.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        let event = parse_event(&words, &events)
+            .map_err(|message| format!("line {}: {}", index + 1, message))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+// one `<frame> <key> down|up` line split into words, which has to come after `events`
+pub fn parse_event(words: &[&str], events: &[InputEvent]) -> Result<InputEvent, String> {
+    let [frame, key, action] = words else {
+        return Err(format!(
+            "expected `<frame> <key> down|up`, got `{}`",
+            words.join(" ")
+        ));
+    };
+
+    let frame = frame
+        .parse()
+        .map_err(|_| format!("invalid frame `{}`", frame))?;
+    let key = u8::from_str_radix(key, 16)
+        .ok()
+        .filter(|&key| key < 16)
+        .ok_or_else(|| format!("invalid key `{}`", key))?;
+    let pressed = match *action {
+        "down" => true,
+        "up" => false,
+        _ => return Err(format!("invalid key action `{}`", action)),
+    };
+    if events.last().is_some_and(|last| last.frame > frame) {
+        return Err("key events are out of order".into());
+    }
+    Ok(InputEvent {
+        frame,
+        key,
+        pressed,
+    })
+}
+
+// runs up to `frames` frames with the scripted input, stopping early if the ROM
+// exits; returns how many frames ran
+pub fn run(machine: &mut Machine, frames: u64, events: &[InputEvent]) -> Result<u64, CpuError> {
+    run_with(machine, frames, events, |_| {})
+}
+
+// the same, calling `on_frame` at the end of every frame that ran
+pub fn run_with(
+    machine: &mut Machine,
+    frames: u64,
+    events: &[InputEvent],
+    mut on_frame: impl FnMut(&Machine),
+) -> Result<u64, CpuError> {
+    let mut events = events.iter().peekable();
+    for frame in 0..frames {
+        if machine.cpu.exited {
+            return Ok(frame);
+        }
+        while let Some(event) = events.next_if(|event| event.frame <= frame) {
+            machine.set_key(event.key as usize, event.pressed);
+        }
+        machine.run_frame()?;
+        on_frame(machine);
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+#[path = "./headless_tests.rs"]
+mod headless_tests;
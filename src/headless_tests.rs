@@ -0,0 +1,77 @@
+use super::*;
+use crate::{assembler::assemble, quirks::Profile};
+
+fn build_machine(source: &str) -> Machine {
+    let profile = Profile::SuperChipModern;
+    let mut machine = Machine::new(profile.platform(), profile.quirks());
+    machine.load_rom(&assemble(source).unwrap().rom);
+    machine
+}
+
+#[test]
+fn test_parse_keys() {
+    let events = parse_keys("# comment\n\n0 5 down\n60 F up # release\n60 a down\n").unwrap();
+    assert_eq!(
+        events,
+        [
+            InputEvent {
+                frame: 0,
+                key: 5,
+                pressed: true
+            },
+            InputEvent {
+                frame: 60,
+                key: 0xF,
+                pressed: false
+            },
+            InputEvent {
+                frame: 60,
+                key: 0xA,
+                pressed: true
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_keys_errors() {
+    assert_eq!(
+        parse_keys("0 5 down\n1 5").unwrap_err(),
+        "line 2: expected `<frame> <key> down|up`, got `1 5`"
+    );
+    assert_eq!(
+        parse_keys("x 5 down").unwrap_err(),
+        "line 1: invalid frame `x`"
+    );
+    assert_eq!(
+        parse_keys("0 10 down").unwrap_err(),
+        "line 1: invalid key `10`"
+    );
+    assert_eq!(
+        parse_keys("0 5 held").unwrap_err(),
+        "line 1: invalid key action `held`"
+    );
+    assert_eq!(
+        parse_keys("9 5 down\n3 5 up").unwrap_err(),
+        "line 2: key events are out of order"
+    );
+}
+
+#[test]
+fn test_run_applies_keys() {
+    // counts instructions with key 5 held
+    let mut machine = build_machine("v1 := 5 loop if v1 key then v2 += 1 again");
+    let events = parse_keys("2 5 down\n4 5 up").unwrap();
+
+    assert_eq!(run(&mut machine, 10, &events).unwrap(), 10);
+    assert!(machine.cpu.v[2] > 0);
+    assert!(!machine.cpu.keypad.keys[5]);
+}
+
+#[test]
+fn test_run_stops_on_exit() {
+    let mut machine = build_machine("v0 := 1 exit");
+
+    assert_eq!(run(&mut machine, 100, &[]).unwrap(), 1);
+    assert!(machine.cpu.exited);
+}
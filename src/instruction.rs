@@ -0,0 +1,519 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+// a decoded opcode, covering CHIP-8, SUPER-CHIP and XO-CHIP
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // 00CN
+    ScrollDown(u8),
+    // 00DN
+    ScrollUp(u8),
+    // 00E0
+    Clear,
+    // 00EE
+    Return,
+    // 00FB
+    ScrollRight,
+    // 00FC
+    ScrollLeft,
+    // 00FD
+    Exit,
+    // 00FE
+    Lores,
+    // 00FF
+    Hires,
+    // 1NNN
+    Jump(u16),
+    // 2NNN
+    Call(u16),
+    // 3XKK
+    SkipEqImm { x: usize, kk: u8 },
+    // 4XKK
+    SkipNeImm { x: usize, kk: u8 },
+    // 5XY0
+    SkipEq { x: usize, y: usize },
+    // 5XY2
+    SaveRange { x: usize, y: usize },
+    // 5XY3
+    LoadRange { x: usize, y: usize },
+    // 6XKK
+    LoadImm { x: usize, kk: u8 },
+    // 7XKK
+    AddImm { x: usize, kk: u8 },
+    // 8XY0
+    Move { x: usize, y: usize },
+    // 8XY1
+    Or { x: usize, y: usize },
+    // 8XY2
+    And { x: usize, y: usize },
+    // 8XY3
+    Xor { x: usize, y: usize },
+    // 8XY4
+    Add { x: usize, y: usize },
+    // 8XY5
+    Sub { x: usize, y: usize },
+    // 8XY6
+    ShiftRight { x: usize, y: usize },
+    // 8XY7
+    SubReverse { x: usize, y: usize },
+    // 8XYE
+    ShiftLeft { x: usize, y: usize },
+    // 9XY0
+    SkipNe { x: usize, y: usize },
+    // ANNN
+    LoadI(u16),
+    // BNNN
+    JumpOffset(u16),
+    // CXKK
+    Random { x: usize, kk: u8 },
+    // DXYN
+    Draw { x: usize, y: usize, n: u8 },
+    // EX9E
+    SkipKey(usize),
+    // EXA1
+    SkipNotKey(usize),
+    // F000, the address is in the word that follows
+    LoadLongI,
+    // FN01
+    Plane(u8),
+    // F002
+    Audio,
+    // FX07
+    GetDelay(usize),
+    // FX0A
+    WaitKey(usize),
+    // FX15
+    SetDelay(usize),
+    // FX18
+    SetSound(usize),
+    // FX1E
+    AddI(usize),
+    // FX29
+    Font(usize),
+    // FX30
+    BigFont(usize),
+    // FX33
+    Bcd(usize),
+    // FX3A
+    Pitch(usize),
+    // FX55
+    Save(usize),
+    // FX65
+    Load(usize),
+    // FX75
+    SaveFlags(usize),
+    // FX85
+    LoadFlags(usize),
+}
+
+// the VIP interpreter's fetch and decode loop, paid by every instruction
+pub const VIP_FETCH_CYCLES: u32 = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Octo,
+    Cowgod,
+}
+
+impl Instruction {
+    pub fn decode(opcode: u16) -> Option<Instruction> {
+        // get opcode parameters
+        let x = ((opcode & 0x0F00) >> 8) as usize;
+        let y = ((opcode & 0x00F0) >> 4) as usize;
+        let nnn = opcode & 0x0FFF;
+        let kk = (opcode & 0x00FF) as u8;
+        let n = (opcode & 0x000F) as u8;
+
+        // split into nibbles
+        let op_1 = (opcode & 0xF000) >> 12;
+        let op_2 = (opcode & 0x0F00) >> 8;
+        let op_3 = (opcode & 0x00F0) >> 4;
+        let op_4 = opcode & 0x000F;
+
+        let instruction = match (op_1, op_2, op_3, op_4) {
+            (0x00, 0x00, 0x0C, _) => Instruction::ScrollDown(n),
+            (0x00, 0x00, 0x0D, _) => Instruction::ScrollUp(n),
+            (0x00, 0x00, 0x0E, 0x00) => Instruction::Clear,
+            (0x00, 0x00, 0x0E, 0x0E) => Instruction::Return,
+            (0x00, 0x00, 0x0F, 0x0B) => Instruction::ScrollRight,
+            (0x00, 0x00, 0x0F, 0x0C) => Instruction::ScrollLeft,
+            (0x00, 0x00, 0x0F, 0x0D) => Instruction::Exit,
+            (0x00, 0x00, 0x0F, 0x0E) => Instruction::Lores,
+            (0x00, 0x00, 0x0F, 0x0F) => Instruction::Hires,
+            (0x01, _, _, _) => Instruction::Jump(nnn),
+            (0x02, _, _, _) => Instruction::Call(nnn),
+            (0x03, _, _, _) => Instruction::SkipEqImm { x, kk },
+            (0x04, _, _, _) => Instruction::SkipNeImm { x, kk },
+            (0x05, _, _, 0x00) => Instruction::SkipEq { x, y },
+            (0x05, _, _, 0x02) => Instruction::SaveRange { x, y },
+            (0x05, _, _, 0x03) => Instruction::LoadRange { x, y },
+            (0x06, _, _, _) => Instruction::LoadImm { x, kk },
+            (0x07, _, _, _) => Instruction::AddImm { x, kk },
+            (0x08, _, _, 0x00) => Instruction::Move { x, y },
+            (0x08, _, _, 0x01) => Instruction::Or { x, y },
+            (0x08, _, _, 0x02) => Instruction::And { x, y },
+            (0x08, _, _, 0x03) => Instruction::Xor { x, y },
+            (0x08, _, _, 0x04) => Instruction::Add { x, y },
+            (0x08, _, _, 0x05) => Instruction::Sub { x, y },
+            (0x08, _, _, 0x06) => Instruction::ShiftRight { x, y },
+            (0x08, _, _, 0x07) => Instruction::SubReverse { x, y },
+            (0x08, _, _, 0x0E) => Instruction::ShiftLeft { x, y },
+            (0x09, _, _, 0x00) => Instruction::SkipNe { x, y },
+            (0x0A, _, _, _) => Instruction::LoadI(nnn),
+            (0x0B, _, _, _) => Instruction::JumpOffset(nnn),
+            (0x0C, _, _, _) => Instruction::Random { x, kk },
+            (0x0D, _, _, _) => Instruction::Draw { x, y, n },
+            (0x0E, _, 0x09, 0x0E) => Instruction::SkipKey(x),
+            (0x0E, _, 0x0A, 0x01) => Instruction::SkipNotKey(x),
+            (0x0F, 0x00, 0x00, 0x00) => Instruction::LoadLongI,
+            (0x0F, _, 0x00, 0x01) => Instruction::Plane(op_2 as u8),
+            (0x0F, 0x00, 0x00, 0x02) => Instruction::Audio,
+            (0x0F, _, 0x00, 0x07) => Instruction::GetDelay(x),
+            (0x0F, _, 0x00, 0x0A) => Instruction::WaitKey(x),
+            (0x0F, _, 0x01, 0x05) => Instruction::SetDelay(x),
+            (0x0F, _, 0x01, 0x08) => Instruction::SetSound(x),
+            (0x0F, _, 0x01, 0x0E) => Instruction::AddI(x),
+            (0x0F, _, 0x02, 0x09) => Instruction::Font(x),
+            (0x0F, _, 0x03, 0x00) => Instruction::BigFont(x),
+            (0x0F, _, 0x03, 0x03) => Instruction::Bcd(x),
+            (0x0F, _, 0x03, 0x0A) => Instruction::Pitch(x),
+            (0x0F, _, 0x05, 0x05) => Instruction::Save(x),
+            (0x0F, _, 0x06, 0x05) => Instruction::Load(x),
+            (0x0F, _, 0x07, 0x05) => Instruction::SaveFlags(x),
+            (0x0F, _, 0x08, 0x05) => Instruction::LoadFlags(x),
+            (_, _, _, _) => return None,
+        };
+        Some(instruction)
+    }
+
+    pub fn encode(self) -> u16 {
+        let xy = |op: u16, x: usize, y: usize, n: u16| op | (x as u16) << 8 | (y as u16) << 4 | n;
+        let xkk = |op: u16, x: usize, kk: u8| op | (x as u16) << 8 | kk as u16;
+        let fx = |x: usize, kk: u16| 0xF000 | (x as u16) << 8 | kk;
+
+        match self {
+            Instruction::ScrollDown(n) => 0x00C0 | n as u16,
+            Instruction::ScrollUp(n) => 0x00D0 | n as u16,
+            Instruction::Clear => 0x00E0,
+            Instruction::Return => 0x00EE,
+            Instruction::ScrollRight => 0x00FB,
+            Instruction::ScrollLeft => 0x00FC,
+            Instruction::Exit => 0x00FD,
+            Instruction::Lores => 0x00FE,
+            Instruction::Hires => 0x00FF,
+            Instruction::Jump(nnn) => 0x1000 | nnn,
+            Instruction::Call(nnn) => 0x2000 | nnn,
+            Instruction::SkipEqImm { x, kk } => xkk(0x3000, x, kk),
+            Instruction::SkipNeImm { x, kk } => xkk(0x4000, x, kk),
+            Instruction::SkipEq { x, y } => xy(0x5000, x, y, 0x0),
+            Instruction::SaveRange { x, y } => xy(0x5000, x, y, 0x2),
+            Instruction::LoadRange { x, y } => xy(0x5000, x, y, 0x3),
+            Instruction::LoadImm { x, kk } => xkk(0x6000, x, kk),
+            Instruction::AddImm { x, kk } => xkk(0x7000, x, kk),
+            Instruction::Move { x, y } => xy(0x8000, x, y, 0x0),
+            Instruction::Or { x, y } => xy(0x8000, x, y, 0x1),
+            Instruction::And { x, y } => xy(0x8000, x, y, 0x2),
+            Instruction::Xor { x, y } => xy(0x8000, x, y, 0x3),
+            Instruction::Add { x, y } => xy(0x8000, x, y, 0x4),
+            Instruction::Sub { x, y } => xy(0x8000, x, y, 0x5),
+            Instruction::ShiftRight { x, y } => xy(0x8000, x, y, 0x6),
+            Instruction::SubReverse { x, y } => xy(0x8000, x, y, 0x7),
+            Instruction::ShiftLeft { x, y } => xy(0x8000, x, y, 0xE),
+            Instruction::SkipNe { x, y } => xy(0x9000, x, y, 0x0),
+            Instruction::LoadI(nnn) => 0xA000 | nnn,
+            Instruction::JumpOffset(nnn) => 0xB000 | nnn,
+            Instruction::Random { x, kk } => xkk(0xC000, x, kk),
+            Instruction::Draw { x, y, n } => xy(0xD000, x, y, n as u16),
+            Instruction::SkipKey(x) => xkk(0xE000, x, 0x9E),
+            Instruction::SkipNotKey(x) => xkk(0xE000, x, 0xA1),
+            Instruction::LoadLongI => 0xF000,
+            Instruction::Plane(n) => fx(n as usize, 0x01),
+            Instruction::Audio => 0xF002,
+            Instruction::GetDelay(x) => fx(x, 0x07),
+            Instruction::WaitKey(x) => fx(x, 0x0A),
+            Instruction::SetDelay(x) => fx(x, 0x15),
+            Instruction::SetSound(x) => fx(x, 0x18),
+            Instruction::AddI(x) => fx(x, 0x1E),
+            Instruction::Font(x) => fx(x, 0x29),
+            Instruction::BigFont(x) => fx(x, 0x30),
+            Instruction::Bcd(x) => fx(x, 0x33),
+            Instruction::Pitch(x) => fx(x, 0x3A),
+            Instruction::Save(x) => fx(x, 0x55),
+            Instruction::Load(x) => fx(x, 0x65),
+            Instruction::SaveFlags(x) => fx(x, 0x75),
+            Instruction::LoadFlags(x) => fx(x, 0x85),
+        }
+    }
+
+    // F000 NNNN is the only instruction that takes up two words
+    pub fn size(self) -> u16 {
+        if self == Instruction::LoadLongI {
+            4
+        } else {
+            2
+        }
+    }
+
+    // the jump or call target, if this instruction transfers control to a fixed address
+    pub fn target(self) -> Option<u16> {
+        match self {
+            Instruction::Jump(nnn) | Instruction::Call(nnn) => Some(nnn),
+            _ => None,
+        }
+    }
+
+    // V registers the instruction may read and write, as bitmasks; quirk-dependent operands count as both
+    pub fn register_accesses(self) -> (u16, u16) {
+        let range = |from: usize, to: usize| {
+            let (low, high) = (from.min(to), from.max(to));
+            (low..=high).fold(0, |mask, reg| mask | 1 << reg)
+        };
+        let vf = 1 << 0xF;
+
+        match self {
+            Instruction::SkipEqImm { x, .. } | Instruction::SkipNeImm { x, .. } => (1 << x, 0),
+            Instruction::SkipEq { x, y } | Instruction::SkipNe { x, y } => (1 << x | 1 << y, 0),
+            Instruction::SaveRange { x, y } => (range(x, y), 0),
+            Instruction::LoadRange { x, y } => (0, range(x, y)),
+            Instruction::LoadImm { x, .. } | Instruction::Random { x, .. } => (0, 1 << x),
+            Instruction::AddImm { x, .. } => (1 << x, 1 << x),
+            Instruction::Move { x, y } => (1 << y, 1 << x),
+            Instruction::Or { x, y }
+            | Instruction::And { x, y }
+            | Instruction::Xor { x, y }
+            | Instruction::Add { x, y }
+            | Instruction::Sub { x, y }
+            | Instruction::ShiftRight { x, y }
+            | Instruction::SubReverse { x, y }
+            | Instruction::ShiftLeft { x, y } => (1 << x | 1 << y, 1 << x | vf),
+            Instruction::JumpOffset(nnn) => (1 | 1 << (nnn >> 8), 0),
+            Instruction::Draw { x, y, .. } => (1 << x | 1 << y, vf),
+            Instruction::SkipKey(x) | Instruction::SkipNotKey(x) => (1 << x, 0),
+            Instruction::GetDelay(x) | Instruction::WaitKey(x) => (0, 1 << x),
+            Instruction::AddI(x) => (1 << x, vf),
+            Instruction::SetDelay(x)
+            | Instruction::SetSound(x)
+            | Instruction::Font(x)
+            | Instruction::BigFont(x)
+            | Instruction::Bcd(x)
+            | Instruction::Pitch(x) => (1 << x, 0),
+            Instruction::Save(x) | Instruction::SaveFlags(x) => (range(0, x), 0),
+            Instruction::Load(x) | Instruction::LoadFlags(x) => (0, range(0, x)),
+            _ => (0, 0),
+        }
+    }
+
+    // COSMAC VIP machine cycles the original interpreter spends on the instruction,
+    // fetch included. the parts that depend on machine state (taken skips, sprite
+    // rows, BCD digits) are added by the cpu. instructions the VIP doesn't have
+    // cost the same as their nearest VIP relative
+    pub fn vip_cycles(self) -> u32 {
+        let registers = |x: usize, y: usize| 14 * (x.abs_diff(y) as u32 + 1);
+        VIP_FETCH_CYCLES
+            + match self {
+                Instruction::Clear
+                | Instruction::ScrollDown(_)
+                | Instruction::ScrollUp(_)
+                | Instruction::ScrollRight
+                | Instruction::ScrollLeft
+                | Instruction::Lores
+                | Instruction::Hires => 3078,
+                Instruction::Return => 10,
+                Instruction::Exit => 10,
+                Instruction::Jump(_) => 12,
+                Instruction::Call(_) => 26,
+                Instruction::SkipEqImm { .. } | Instruction::SkipNeImm { .. } => 10,
+                Instruction::SkipEq { .. } | Instruction::SkipNe { .. } => 14,
+                Instruction::SaveRange { x, y } | Instruction::LoadRange { x, y } => {
+                    14 + registers(x, y)
+                }
+                Instruction::LoadImm { .. } => 6,
+                Instruction::AddImm { .. } => 10,
+                Instruction::Move { .. }
+                | Instruction::Or { .. }
+                | Instruction::And { .. }
+                | Instruction::Xor { .. }
+                | Instruction::Add { .. }
+                | Instruction::Sub { .. }
+                | Instruction::ShiftRight { .. }
+                | Instruction::SubReverse { .. }
+                | Instruction::ShiftLeft { .. } => 44,
+                Instruction::LoadI(_) | Instruction::LoadLongI => 12,
+                Instruction::JumpOffset(_) => 22,
+                Instruction::Random { .. } => 36,
+                Instruction::Draw { .. } => 26,
+                Instruction::SkipKey(_) | Instruction::SkipNotKey(_) => 14,
+                Instruction::Plane(_) | Instruction::Pitch(_) => 10,
+                Instruction::Audio => 14 + registers(0, 15),
+                Instruction::GetDelay(_) | Instruction::SetDelay(_) | Instruction::SetSound(_) => {
+                    10
+                }
+                Instruction::WaitKey(_) => 18,
+                Instruction::AddI(_) | Instruction::Font(_) | Instruction::BigFont(_) => 16,
+                Instruction::Bcd(_) => 84,
+                Instruction::Save(x)
+                | Instruction::Load(x)
+                | Instruction::SaveFlags(x)
+                | Instruction::LoadFlags(x) => 14 + registers(0, x),
+            }
+    }
+
+    // formats the instruction with jump and call targets replaced by label names where known
+    pub fn format(self, syntax: Syntax, labels: &BTreeMap<u16, String>) -> String {
+        let addr = |nnn: u16| match labels.get(&nnn) {
+            Some(label) => label.clone(),
+            None => format!("0x{:03X}", nnn),
+        };
+        match syntax {
+            Syntax::Octo => self.octo(addr),
+            Syntax::Cowgod => self.cowgod(addr),
+        }
+    }
+
+    fn octo(self, addr: impl Fn(u16) -> String) -> String {
+        match self {
+            Instruction::ScrollDown(n) => format!("scroll-down {}", n),
+            Instruction::ScrollUp(n) => format!("scroll-up {}", n),
+            Instruction::Clear => "clear".to_string(),
+            Instruction::Return => "return".to_string(),
+            Instruction::ScrollRight => "scroll-right".to_string(),
+            Instruction::ScrollLeft => "scroll-left".to_string(),
+            Instruction::Exit => "exit".to_string(),
+            Instruction::Lores => "lores".to_string(),
+            Instruction::Hires => "hires".to_string(),
+            Instruction::Jump(nnn) => format!("jump {}", addr(nnn)),
+            Instruction::Call(nnn) => format!(":call {}", addr(nnn)),
+            // Octo's conditionals describe when the next instruction runs, the opposite of the skip
+            Instruction::SkipEqImm { x, kk } => format!("if v{:x} != 0x{:02X} then", x, kk),
+            Instruction::SkipNeImm { x, kk } => format!("if v{:x} == 0x{:02X} then", x, kk),
+            Instruction::SkipEq { x, y } => format!("if v{:x} != v{:x} then", x, y),
+            Instruction::SaveRange { x, y } => format!("save v{:x} - v{:x}", x, y),
+            Instruction::LoadRange { x, y } => format!("load v{:x} - v{:x}", x, y),
+            Instruction::LoadImm { x, kk } => format!("v{:x} := 0x{:02X}", x, kk),
+            Instruction::AddImm { x, kk } => format!("v{:x} += 0x{:02X}", x, kk),
+            Instruction::Move { x, y } => format!("v{:x} := v{:x}", x, y),
+            Instruction::Or { x, y } => format!("v{:x} |= v{:x}", x, y),
+            Instruction::And { x, y } => format!("v{:x} &= v{:x}", x, y),
+            Instruction::Xor { x, y } => format!("v{:x} ^= v{:x}", x, y),
+            Instruction::Add { x, y } => format!("v{:x} += v{:x}", x, y),
+            Instruction::Sub { x, y } => format!("v{:x} -= v{:x}", x, y),
+            Instruction::ShiftRight { x, y } => format!("v{:x} >>= v{:x}", x, y),
+            Instruction::SubReverse { x, y } => format!("v{:x} =- v{:x}", x, y),
+            Instruction::ShiftLeft { x, y } => format!("v{:x} <<= v{:x}", x, y),
+            Instruction::SkipNe { x, y } => format!("if v{:x} == v{:x} then", x, y),
+            Instruction::LoadI(nnn) => format!("i := 0x{:03X}", nnn),
+            Instruction::JumpOffset(nnn) => format!("jump0 {}", addr(nnn)),
+            Instruction::Random { x, kk } => format!("v{:x} := random 0x{:02X}", x, kk),
+            Instruction::Draw { x, y, n } => format!("sprite v{:x} v{:x} {}", x, y, n),
+            Instruction::SkipKey(x) => format!("if v{:x} -key then", x),
+            Instruction::SkipNotKey(x) => format!("if v{:x} key then", x),
+            Instruction::LoadLongI => "i := long".to_string(),
+            Instruction::Plane(n) => format!("plane {}", n),
+            Instruction::Audio => "audio".to_string(),
+            Instruction::GetDelay(x) => format!("v{:x} := delay", x),
+            Instruction::WaitKey(x) => format!("v{:x} := key", x),
+            Instruction::SetDelay(x) => format!("delay := v{:x}", x),
+            Instruction::SetSound(x) => format!("buzzer := v{:x}", x),
+            Instruction::AddI(x) => format!("i += v{:x}", x),
+            Instruction::Font(x) => format!("i := hex v{:x}", x),
+            Instruction::BigFont(x) => format!("i := bighex v{:x}", x),
+            Instruction::Bcd(x) => format!("bcd v{:x}", x),
+            Instruction::Pitch(x) => format!("pitch := v{:x}", x),
+            Instruction::Save(x) => format!("save v{:x}", x),
+            Instruction::Load(x) => format!("load v{:x}", x),
+            Instruction::SaveFlags(x) => format!("saveflags v{:x}", x),
+            Instruction::LoadFlags(x) => format!("loadflags v{:x}", x),
+        }
+    }
+
+    fn cowgod(self, addr: impl Fn(u16) -> String) -> String {
+        match self {
+            Instruction::ScrollDown(n) => format!("SCD {}", n),
+            Instruction::ScrollUp(n) => format!("SCU {}", n),
+            Instruction::Clear => "CLS".to_string(),
+            Instruction::Return => "RET".to_string(),
+            Instruction::ScrollRight => "SCR".to_string(),
+            Instruction::ScrollLeft => "SCL".to_string(),
+            Instruction::Exit => "EXIT".to_string(),
+            Instruction::Lores => "LOW".to_string(),
+            Instruction::Hires => "HIGH".to_string(),
+            Instruction::Jump(nnn) => format!("JP {}", addr(nnn)),
+            Instruction::Call(nnn) => format!("CALL {}", addr(nnn)),
+            Instruction::SkipEqImm { x, kk } => format!("SE V{:X}, 0x{:02X}", x, kk),
+            Instruction::SkipNeImm { x, kk } => format!("SNE V{:X}, 0x{:02X}", x, kk),
+            Instruction::SkipEq { x, y } => format!("SE V{:X}, V{:X}", x, y),
+            Instruction::SaveRange { x, y } => format!("LD [I], V{:X}-V{:X}", x, y),
+            Instruction::LoadRange { x, y } => format!("LD V{:X}-V{:X}, [I]", x, y),
+            Instruction::LoadImm { x, kk } => format!("LD V{:X}, 0x{:02X}", x, kk),
+            Instruction::AddImm { x, kk } => format!("ADD V{:X}, 0x{:02X}", x, kk),
+            Instruction::Move { x, y } => format!("LD V{:X}, V{:X}", x, y),
+            Instruction::Or { x, y } => format!("OR V{:X}, V{:X}", x, y),
+            Instruction::And { x, y } => format!("AND V{:X}, V{:X}", x, y),
+            Instruction::Xor { x, y } => format!("XOR V{:X}, V{:X}", x, y),
+            Instruction::Add { x, y } => format!("ADD V{:X}, V{:X}", x, y),
+            Instruction::Sub { x, y } => format!("SUB V{:X}, V{:X}", x, y),
+            Instruction::ShiftRight { x, y } => format!("SHR V{:X}, V{:X}", x, y),
+            Instruction::SubReverse { x, y } => format!("SUBN V{:X}, V{:X}", x, y),
+            Instruction::ShiftLeft { x, y } => format!("SHL V{:X}, V{:X}", x, y),
+            Instruction::SkipNe { x, y } => format!("SNE V{:X}, V{:X}", x, y),
+            Instruction::LoadI(nnn) => format!("LD I, 0x{:03X}", nnn),
+            Instruction::JumpOffset(nnn) => format!("JP V0, {}", addr(nnn)),
+            Instruction::Random { x, kk } => format!("RND V{:X}, 0x{:02X}", x, kk),
+            Instruction::Draw { x, y, n } => format!("DRW V{:X}, V{:X}, {}", x, y, n),
+            Instruction::SkipKey(x) => format!("SKP V{:X}", x),
+            Instruction::SkipNotKey(x) => format!("SKNP V{:X}", x),
+            Instruction::LoadLongI => "LD I, LONG".to_string(),
+            Instruction::Plane(n) => format!("PLANE {}", n),
+            Instruction::Audio => "AUDIO".to_string(),
+            Instruction::GetDelay(x) => format!("LD V{:X}, DT", x),
+            Instruction::WaitKey(x) => format!("LD V{:X}, K", x),
+            Instruction::SetDelay(x) => format!("LD DT, V{:X}", x),
+            Instruction::SetSound(x) => format!("LD ST, V{:X}", x),
+            Instruction::AddI(x) => format!("ADD I, V{:X}", x),
+            Instruction::Font(x) => format!("LD F, V{:X}", x),
+            Instruction::BigFont(x) => format!("LD HF, V{:X}", x),
+            Instruction::Bcd(x) => format!("LD B, V{:X}", x),
+            Instruction::Pitch(x) => format!("PITCH V{:X}", x),
+            Instruction::Save(x) => format!("LD [I], V{:X}", x),
+            Instruction::Load(x) => format!("LD V{:X}, [I]", x),
+            Instruction::SaveFlags(x) => format!("LD R, V{:X}", x),
+            Instruction::LoadFlags(x) => format!("LD V{:X}, R", x),
+        }
+    }
+}
+
+// `{}` prints Octo syntax, `{:#}` prints classic Cowgod syntax
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let syntax = if f.alternate() {
+            Syntax::Cowgod
+        } else {
+            Syntax::Octo
+        };
+        f.write_str(&self.format(syntax, &BTreeMap::new()))
+    }
+}
+
+impl fmt::Display for Syntax {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Syntax::Octo => "octo",
+            Syntax::Cowgod => "cowgod",
+        })
+    }
+}
+
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Syntax, String> {
+        match s {
+            "octo" => Ok(Syntax::Octo),
+            "cowgod" => Ok(Syntax::Cowgod),
+            _ => Err(format!("unknown syntax `{}` (expected octo or cowgod)", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./instruction_tests.rs"]
+mod instruction_tests;
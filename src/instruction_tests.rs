@@ -0,0 +1,102 @@
+use super::*;
+
+#[test]
+fn test_decode() {
+    assert_eq!(Instruction::decode(0x00E0), Some(Instruction::Clear));
+    assert_eq!(
+        Instruction::decode(0x00C4),
+        Some(Instruction::ScrollDown(4))
+    );
+    assert_eq!(Instruction::decode(0x2ABC), Some(Instruction::Call(0xABC)));
+    assert_eq!(
+        Instruction::decode(0x8AB6),
+        Some(Instruction::ShiftRight { x: 0xA, y: 0xB })
+    );
+    assert_eq!(
+        Instruction::decode(0xD12F),
+        Some(Instruction::Draw { x: 1, y: 2, n: 0xF })
+    );
+    assert_eq!(Instruction::decode(0xF301), Some(Instruction::Plane(3)));
+    assert_eq!(Instruction::decode(0xF000), Some(Instruction::LoadLongI));
+    assert_eq!(Instruction::decode(0x0123), None);
+    assert_eq!(Instruction::decode(0x5AB1), None);
+    assert_eq!(Instruction::decode(0xE1FF), None);
+    assert_eq!(Instruction::decode(0xF100), None);
+}
+
+#[test]
+fn test_encode_round_trip() {
+    // every opcode that decodes encodes back to itself
+    for opcode in 0..=u16::MAX {
+        if let Some(instruction) = Instruction::decode(opcode) {
+            assert_eq!(instruction.encode(), opcode, "{:04X}", opcode);
+        }
+    }
+}
+
+#[test]
+fn test_octo_syntax() {
+    let text = |opcode| Instruction::decode(opcode).unwrap().to_string();
+
+    assert_eq!(text(0x00EE), "return");
+    assert_eq!(text(0x1208), "jump 0x208");
+    assert_eq!(text(0x3A05), "if va != 0x05 then");
+    assert_eq!(text(0x9120), "if v1 == v2 then");
+    assert_eq!(text(0x5123), "load v1 - v2");
+    assert_eq!(text(0x8127), "v1 =- v2");
+    assert_eq!(text(0xC30F), "v3 := random 0x0F");
+    assert_eq!(text(0xD125), "sprite v1 v2 5");
+    assert_eq!(text(0xE19E), "if v1 -key then");
+    assert_eq!(text(0xF230), "i := bighex v2");
+    assert_eq!(text(0xF518), "buzzer := v5");
+}
+
+#[test]
+fn test_cowgod_syntax() {
+    let text = |opcode| format!("{:#}", Instruction::decode(opcode).unwrap());
+
+    assert_eq!(text(0x00EE), "RET");
+    assert_eq!(text(0x1208), "JP 0x208");
+    assert_eq!(text(0x3A05), "SE VA, 0x05");
+    assert_eq!(text(0xB300), "JP V0, 0x300");
+    assert_eq!(text(0xD125), "DRW V1, V2, 5");
+    assert_eq!(text(0xE1A1), "SKNP V1");
+    assert_eq!(text(0xF20A), "LD V2, K");
+    assert_eq!(text(0xF565), "LD V5, [I]");
+}
+
+#[test]
+fn test_format_labels() {
+    let labels = BTreeMap::from([(0x208, "sub_0208".to_string())]);
+
+    assert_eq!(
+        Instruction::Call(0x208).format(Syntax::Octo, &labels),
+        ":call sub_0208"
+    );
+    assert_eq!(
+        Instruction::Call(0x208).format(Syntax::Cowgod, &labels),
+        "CALL sub_0208"
+    );
+    assert_eq!(
+        Instruction::Jump(0x20A).format(Syntax::Octo, &labels),
+        "jump 0x20A"
+    );
+}
+
+#[test]
+fn test_register_accesses() {
+    assert_eq!(
+        Instruction::Move { x: 1, y: 2 }.register_accesses(),
+        (0b100, 0b10)
+    );
+    assert_eq!(
+        Instruction::Add { x: 0, y: 1 }.register_accesses(),
+        (0b11, 0x8001)
+    );
+    assert_eq!(Instruction::Save(2).register_accesses(), (0b111, 0));
+    assert_eq!(
+        Instruction::LoadRange { x: 3, y: 1 }.register_accesses(),
+        (0, 0b1110)
+    );
+    assert_eq!(Instruction::Clear.register_accesses(), (0, 0));
+}
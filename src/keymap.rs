@@ -0,0 +1,199 @@
+use std::{cmp::Ordering, collections::BTreeMap, fmt};
+
+use crate::config::Config;
+
+// the COSMAC VIP keypad, as it's laid out on the 1234/QWER/ASDF/ZXCV block
+const KEYPAD: [[u8; 4]; 4] = [
+    [0x1, 0x2, 0x3, 0xC],
+    [0x4, 0x5, 0x6, 0xD],
+    [0x7, 0x8, 0x9, 0xE],
+    [0xA, 0x0, 0xB, 0xF],
+];
+const DEFAULT_KEYS: [[&str; 4]; 4] = [
+    ["1", "2", "3", "4"],
+    ["Q", "W", "E", "R"],
+    ["A", "S", "D", "F"],
+    ["Z", "X", "C", "V"],
+];
+// the d-pad and left stick move like WASD, the face buttons are the keys
+// around W that most games use for actions
+const DEFAULT_GAMEPAD: [(&str, u8); 12] = [
+    ("dpup", 0x5),
+    ("dpleft", 0x7),
+    ("dpdown", 0x8),
+    ("dpright", 0x9),
+    ("lefty-", 0x5),
+    ("leftx-", 0x7),
+    ("lefty+", 0x8),
+    ("leftx+", 0x9),
+    ("a", 0x6),
+    ("b", 0x4),
+    ("x", 0xA),
+    ("y", 0xB),
+];
+pub const DEFAULT_DEADZONE: f32 = 0.3;
+
+// a host keyboard key or gamepad control by its SDL name, which the frontend
+// resolves. names compare without case, the way SDL looks them up
+#[derive(Debug, Clone)]
+pub enum HostKey {
+    Keycode(String),
+    Scancode(String),
+    // a button, or an axis with a `+` or `-` direction
+    Gamepad(String),
+}
+
+impl HostKey {
+    fn sort_key(&self) -> (u8, String) {
+        match self {
+            HostKey::Keycode(name) => (0, name.to_ascii_lowercase()),
+            HostKey::Scancode(name) => (1, name.to_ascii_lowercase()),
+            HostKey::Gamepad(name) => (2, name.to_ascii_lowercase()),
+        }
+    }
+}
+
+impl PartialEq for HostKey {
+    fn eq(&self, other: &HostKey) -> bool {
+        self.sort_key() == other.sort_key()
+    }
+}
+
+impl Eq for HostKey {}
+
+impl PartialOrd for HostKey {
+    fn partial_cmp(&self, other: &HostKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HostKey {
+    fn cmp(&self, other: &HostKey) -> Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
+impl fmt::Display for HostKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HostKey::Keycode(name) => write!(f, "{}", name),
+            HostKey::Scancode(name) => write!(f, "scancode {}", name),
+            HostKey::Gamepad(name) => write!(f, "gamepad {}", name),
+        }
+    }
+}
+
+// which hex keys each host key presses
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    bindings: BTreeMap<HostKey, Vec<u8>>,
+    pub deadzone: f32,
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        let mut bindings = BTreeMap::new();
+        for (keys, names) in KEYPAD.iter().zip(DEFAULT_KEYS) {
+            for (&key, name) in keys.iter().zip(names) {
+                bindings.insert(HostKey::Keycode(name.to_string()), vec![key]);
+            }
+        }
+        for (name, key) in DEFAULT_GAMEPAD {
+            bindings.insert(HostKey::Gamepad(name.to_string()), vec![key]);
+        }
+        Keymap {
+            bindings,
+            deadzone: DEFAULT_DEADZONE,
+        }
+    }
+}
+
+impl Keymap {
+    // layers a config over the current bindings
+    pub fn apply(&mut self, config: &Config) {
+        if config.replace_keys {
+            self.bindings
+                .retain(|host_key, _| matches!(host_key, HostKey::Gamepad(_)));
+        }
+        if config.replace_gamepad {
+            self.bindings
+                .retain(|host_key, _| !matches!(host_key, HostKey::Gamepad(_)));
+        }
+        if let Some(deadzone) = config.gamepad_deadzone {
+            self.deadzone = deadzone;
+        }
+        let keycodes = config
+            .keys
+            .iter()
+            .map(|(name, keys)| (HostKey::Keycode(name.clone()), keys));
+        let scancodes = config
+            .scancodes
+            .iter()
+            .map(|(name, keys)| (HostKey::Scancode(name.clone()), keys));
+        let gamepad = config
+            .gamepad
+            .iter()
+            .map(|(name, keys)| (HostKey::Gamepad(name.clone()), keys));
+        for (host_key, keys) in keycodes.chain(scancodes).chain(gamepad) {
+            // removed first, so the new spelling of the name is the one kept
+            self.bindings.remove(&host_key);
+            if !keys.keys().is_empty() {
+                self.bindings.insert(host_key, keys.keys().to_vec());
+            }
+        }
+    }
+
+    pub fn get(&self, host_key: &HostKey) -> &[u8] {
+        self.bindings.get(host_key).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn bindings(&self) -> impl Iterator<Item = (&HostKey, &[u8])> {
+        self.bindings
+            .iter()
+            .map(|(host_key, keys)| (host_key, keys.as_slice()))
+    }
+
+    // the host keys bound to a hex key
+    pub fn host_keys(&self, key: u8) -> Vec<&HostKey> {
+        self.bindings()
+            .filter(|(_, keys)| keys.contains(&key))
+            .map(|(host_key, _)| host_key)
+            .collect()
+    }
+}
+
+// the end of an axis a stick or trigger is pushed towards, `-` or `+`, or None
+// while it's inside the deadzone
+pub fn axis_direction(value: i16, deadzone: f32) -> Option<char> {
+    let position = value as f32 / i16::MAX as f32;
+    if position > deadzone {
+        Some('+')
+    } else if position < -deadzone {
+        Some('-')
+    } else {
+        None
+    }
+}
+
+// one line per hex key, in keypad order
+impl fmt::Display for Keymap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for key in KEYPAD.iter().flatten() {
+            let host_keys: Vec<String> = self
+                .host_keys(*key)
+                .iter()
+                .map(|host_key| host_key.to_string())
+                .collect();
+            if host_keys.is_empty() {
+                writeln!(f, "{:X}: -", key)?;
+            } else {
+                writeln!(f, "{:X}: {}", key, host_keys.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "./keymap_tests.rs"]
+mod keymap_tests;
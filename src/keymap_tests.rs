@@ -0,0 +1,93 @@
+use super::*;
+
+fn keycode(name: &str) -> HostKey {
+    HostKey::Keycode(name.to_string())
+}
+
+fn gamepad(name: &str) -> HostKey {
+    HostKey::Gamepad(name.to_string())
+}
+
+#[test]
+fn test_default() {
+    let keymap = Keymap::default();
+
+    assert_eq!(keymap.get(&keycode("1")), [0x1]);
+    assert_eq!(keymap.get(&keycode("4")), [0xC]);
+    assert_eq!(keymap.get(&keycode("X")), [0x0]);
+    assert_eq!(keymap.get(&keycode("v")), [0xF]);
+    assert_eq!(keymap.get(&keycode("Up")), [] as [u8; 0]);
+    assert_eq!(keymap.get(&gamepad("dpup")), [0x5]);
+    assert_eq!(keymap.get(&gamepad("leftx+")), [0x9]);
+    assert_eq!(keymap.bindings().count(), 16 + 12);
+    assert_eq!(keymap.deadzone, DEFAULT_DEADZONE);
+}
+
+#[test]
+fn test_layers() {
+    let mut keymap = Keymap::default();
+    keymap.apply(&"[keys]\nUp = 5\nq = [4, 6]\nZ = []".parse().unwrap());
+
+    assert_eq!(keymap.get(&keycode("Up")), [5]);
+    assert_eq!(keymap.get(&keycode("Q")), [4, 6]);
+    assert_eq!(keymap.get(&keycode("Z")), [] as [u8; 0]);
+    assert_eq!(keymap.get(&keycode("W")), [5]);
+
+    keymap.apply(&"replace-keys = true\n[scancodes]\nA = 0xA".parse().unwrap());
+    assert_eq!(keymap.get(&keycode("Up")), [] as [u8; 0]);
+    assert_eq!(keymap.get(&HostKey::Scancode("a".to_string())), [0xA]);
+    assert_eq!(keymap.get(&keycode("A")), [] as [u8; 0]);
+}
+
+#[test]
+fn test_display() {
+    let mut keymap = Keymap::default();
+    keymap.apply(
+        &"replace-gamepad = true\n[keys]\nUp = 5\nq = [4, 6]\n[scancodes]\nSpace = 5"
+            .parse()
+            .unwrap(),
+    );
+    let text = keymap.to_string();
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(lines.len(), 16);
+    assert_eq!(&lines[..2], ["1: 1", "2: 2"]);
+    assert_eq!(lines[4], "4: q");
+    assert_eq!(lines[5], "5: Up, W, scancode Space");
+    assert_eq!(lines[6], "6: E, q");
+
+    keymap.apply(&"[keys]\nV = []".parse().unwrap());
+    assert!(keymap.to_string().ends_with("F: -\n"));
+}
+
+#[test]
+fn test_gamepad_layers() {
+    let mut keymap = Keymap::default();
+    keymap.apply(
+        &"replace-keys = true\ngamepad-deadzone = 0.5\n[gamepad]\nDPUP = 2\nstart = [1, 2]"
+            .parse()
+            .unwrap(),
+    );
+
+    // replacing the keyboard bindings leaves the gamepad's alone
+    assert_eq!(keymap.get(&keycode("W")), [] as [u8; 0]);
+    assert_eq!(keymap.get(&gamepad("dpup")), [2]);
+    assert_eq!(keymap.get(&gamepad("start")), [1, 2]);
+    assert_eq!(keymap.get(&gamepad("a")), [6]);
+    assert_eq!(keymap.deadzone, 0.5);
+
+    keymap.apply(&"replace-gamepad = true\n[keys]\nUp = 5".parse().unwrap());
+    assert_eq!(keymap.get(&gamepad("dpup")), [] as [u8; 0]);
+    assert_eq!(keymap.get(&keycode("up")), [5]);
+    assert_eq!(keymap.deadzone, 0.5);
+}
+
+#[test]
+fn test_axis_direction() {
+    assert_eq!(axis_direction(0, 0.3), None);
+    assert_eq!(axis_direction(9000, 0.3), None);
+    assert_eq!(axis_direction(-9000, 0.3), None);
+    assert_eq!(axis_direction(12000, 0.3), Some('+'));
+    assert_eq!(axis_direction(i16::MIN, 0.3), Some('-'));
+    assert_eq!(axis_direction(i16::MAX, 0.0), Some('+'));
+}
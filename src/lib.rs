@@ -0,0 +1,47 @@
+pub mod assembler;
+pub mod audio;
+pub mod config;
+pub mod cpu;
+pub mod debugger;
+pub mod disasm;
+pub mod display;
+pub mod golden;
+pub mod headless;
+pub mod instruction;
+pub mod keymap;
+pub mod keypad;
+pub mod movie;
+pub mod palette;
+pub mod persistence;
+pub mod quirks;
+pub mod render;
+pub mod rewind;
+pub mod rng;
+pub mod romdb;
+pub mod savestate;
+pub mod scheduler;
+pub mod screenshot;
+
+mod machine;
+
+pub use machine::{
+    hash_rom, Machine, RomHash, Timing, INSTRUCTIONS_PER_SECOND, TIMER_FREQUENCY,
+    VIP_CYCLES_PER_FRAME,
+};
+
+// hashes are written as 40 lowercase hex digits in movies and the ROM database
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+pub(crate) fn unhex(text: &str) -> Result<RomHash, String> {
+    let invalid = || format!("invalid hash `{}`", text);
+    if text.len() != 40 || !text.is_ascii() {
+        return Err(invalid());
+    }
+    let mut hash = [0; 20];
+    for (index, byte) in hash.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[index * 2..index * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(hash)
+}
@@ -0,0 +1,191 @@
+use std::{fmt, str::FromStr};
+
+use sha1::{Digest, Sha1};
+
+use crate::{
+    audio::Pattern,
+    cpu::{Cpu, CpuError, Platform},
+    display::Display,
+    quirks::Quirks,
+    savestate::{self, StateError},
+};
+
+// the default speed, frontends can change it with Machine::ips
+pub const INSTRUCTIONS_PER_SECOND: u32 = 1000;
+pub const TIMER_FREQUENCY: u32 = 60;
+// a COSMAC VIP frame is 3668 machine cycles, the display's DMA takes 1024 of
+// them and its interrupt routine about 72, leaving the rest for the interpreter
+pub const VIP_CYCLES_PER_FRAME: u64 = 2572;
+
+// how much a frame runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timing {
+    // a fixed number of instructions a second, Machine::ips
+    Ips,
+    // the COSMAC VIP's cycle budget, so slow instructions and big sprites take
+    // as long as they did on the original interpreter
+    Vip,
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Timing::Ips => "ips",
+            Timing::Vip => "vip",
+        })
+    }
+}
+
+impl FromStr for Timing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Timing, String> {
+        match s {
+            "ips" => Ok(Timing::Ips),
+            "vip" => Ok(Timing::Vip),
+            _ => Err(format!("unknown timing `{}` (expected ips or vip)", s)),
+        }
+    }
+}
+
+// SHA-1 of a ROM image
+pub type RomHash = [u8; 20];
+
+pub fn hash_rom(rom: &[u8]) -> RomHash {
+    Sha1::digest(rom).into()
+}
+
+// frontend-facing wrapper around the cpu, one frame is one 60 Hz timer tick
+pub struct Machine {
+    pub cpu: Cpu,
+    // instructions per second, spread over the frames as evenly as whole cycles allow
+    pub ips: u32,
+    pub timing: Timing,
+    // cycles owed from earlier frames, in 1/TIMER_FREQUENCY steps
+    cycle_remainder: u32,
+    // where the current frame stops, on the cpu's instruction or VIP cycle count
+    frame_end: u64,
+    rom_hash: RomHash,
+}
+
+impl Machine {
+    pub fn new(platform: Platform, quirks: Quirks) -> Machine {
+        let mut cpu = Cpu::new(quirks);
+        cpu.set_platform(platform);
+        cpu.reset();
+        Machine {
+            cpu,
+            ips: INSTRUCTIONS_PER_SECOND,
+            timing: Timing::Ips,
+            cycle_remainder: 0,
+            frame_end: 0,
+            rom_hash: hash_rom(&[]),
+        }
+    }
+
+    pub fn load_rom(&mut self, rom: &[u8]) {
+        self.cpu.reset();
+        self.cpu.load(rom);
+        self.rom_hash = hash_rom(rom);
+    }
+
+    pub fn rom_hash(&self) -> RomHash {
+        self.rom_hash
+    }
+
+    pub fn save_state(&self) -> Vec<u8> {
+        savestate::save(self)
+    }
+
+    // leaves the machine untouched when the state can't be loaded
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), StateError> {
+        savestate::load(self, data)
+    }
+
+    pub fn step(&mut self) -> Result<(), CpuError> {
+        self.cpu.execute()
+    }
+
+    // how many instructions the next frame gets, e.g. 1000 IPS alternates 16 and 17
+    fn cycles_this_frame(&mut self) -> u32 {
+        let total = self.cycle_remainder + self.ips;
+        self.cycle_remainder = total % TIMER_FREQUENCY;
+        total / TIMER_FREQUENCY
+    }
+
+    fn elapsed(&self) -> u64 {
+        match self.timing {
+            Timing::Ips => self.cpu.instructions,
+            Timing::Vip => self.cpu.vip_cycles,
+        }
+    }
+
+    // gives the next frame its budget. an instruction that ran past the end of
+    // the last frame comes out of this one, time left over isn't carried
+    pub fn start_frame(&mut self) {
+        // keys may have changed between frames without going through set_key
+        self.cpu.poll_key_wait();
+        let budget = match self.timing {
+            Timing::Ips => self.cycles_this_frame() as u64,
+            Timing::Vip => VIP_CYCLES_PER_FRAME,
+        };
+        self.frame_end = self.frame_end.min(self.elapsed()) + budget;
+    }
+
+    // whether the frame started by start_frame has budget left for another
+    // instruction; a draw waiting for vblank or an FX0A waiting for a key
+    // gives up the rest of it
+    pub fn frame_has_time(&self) -> bool {
+        !self.cpu.exited
+            && !self.cpu.vblank_wait
+            && !self.cpu.waiting_for_key()
+            && self.elapsed() < self.frame_end
+    }
+
+    pub fn run_frame(&mut self) -> Result<(), CpuError> {
+        self.start_frame();
+        while self.frame_has_time() {
+            self.step()?;
+        }
+        self.cpu.decrement_timers();
+        Ok(())
+    }
+
+    pub fn framebuffer(&self) -> &Display {
+        &self.cpu.display
+    }
+
+    // an FX0A sees every transition, even a press and release between two
+    // frames; keys past F don't exist and are ignored
+    pub fn set_key(&mut self, key: usize, pressed: bool) {
+        if key >= self.cpu.keypad.keys.len() {
+            return;
+        }
+        if pressed {
+            self.cpu.keypad.key_down(key);
+        } else {
+            self.cpu.keypad.key_up(key);
+        }
+        self.cpu.poll_key_wait();
+    }
+
+    // whether the ROM is stuck on FX0A until a key is pressed and released,
+    // frontends can show that they're waiting for input
+    pub fn waiting_for_key(&self) -> bool {
+        self.cpu.waiting_for_key()
+    }
+
+    pub fn sound_active(&self) -> bool {
+        self.cpu.st > 0
+    }
+
+    // what XO-CHIP ROMs play while the sound timer runs, None on the other
+    // platforms, which only have the beeper
+    pub fn audio_pattern(&self) -> Option<Pattern> {
+        (self.cpu.platform == Platform::XoChip).then_some((self.cpu.audio_pattern, self.cpu.pitch))
+    }
+}
+
+#[cfg(test)]
+#[path = "./machine_tests.rs"]
+mod machine_tests;
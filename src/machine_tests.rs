@@ -0,0 +1,180 @@
+use super::*;
+
+fn build_machine(rom: &[u8]) -> Machine {
+    let mut machine = Machine::new(Platform::Chip8, Quirks::default());
+    machine.load_rom(rom);
+    machine
+}
+
+#[test]
+fn test_load_rom() {
+    let machine = build_machine(&[0x60, 0x05]);
+
+    assert_eq!(machine.cpu.pc, 0x200);
+    assert_eq!(machine.cpu.memory[0x200..0x202], [0x60, 0x05]);
+    // reloading resets the font along with everything else
+    assert_eq!(machine.cpu.memory[0], 0xF0);
+}
+
+#[test]
+fn test_step() {
+    let mut machine = build_machine(&[0x60, 0x05, 0x61, 0x07]);
+    machine.step().unwrap();
+
+    assert_eq!(machine.cpu.v[0], 5);
+    assert_eq!(machine.cpu.v[1], 0);
+    assert_eq!(machine.cpu.pc, 0x202);
+}
+
+#[test]
+fn test_run_frame() {
+    // V0 = 3, DT = V0, ST = V0, then spin on 1206
+    let mut machine = build_machine(&[0x60, 0x03, 0xF0, 0x15, 0xF0, 0x18, 0x12, 0x06]);
+    machine.run_frame().unwrap();
+
+    assert_eq!(machine.cpu.dt, 2);
+    assert!(machine.sound_active());
+
+    machine.run_frame().unwrap();
+    machine.run_frame().unwrap();
+
+    assert_eq!(machine.cpu.dt, 0);
+    assert!(!machine.sound_active());
+}
+
+#[test]
+fn test_cycles_this_frame() {
+    let mut machine = build_machine(&[]);
+    let cycles: Vec<u32> = (0..6).map(|_| machine.cycles_this_frame()).collect();
+    assert_eq!(cycles, [16, 17, 17, 16, 17, 17]);
+
+    // a second's worth of frames runs exactly the set speed
+    machine.ips = 700;
+    let total: u32 = (0..TIMER_FREQUENCY)
+        .map(|_| machine.cycles_this_frame())
+        .sum();
+    assert_eq!(total, 700);
+}
+
+#[test]
+fn test_run_frame_error() {
+    let mut machine = build_machine(&[0x00, 0xEE]);
+
+    assert_eq!(
+        machine.run_frame(),
+        Err(CpuError::StackUnderflow { pc: 0x200 })
+    );
+}
+
+#[test]
+fn test_run_frame_stops_on_exit() {
+    let mut machine = build_machine(&[0x00, 0xFD]);
+    machine.run_frame().unwrap();
+
+    assert!(machine.cpu.exited);
+    assert_eq!(machine.cpu.pc, 0x202);
+}
+
+#[test]
+fn test_set_key() {
+    let mut machine = build_machine(&[]);
+    machine.set_key(0xA, true);
+
+    assert!(machine.cpu.keypad.keys[0xA]);
+
+    machine.set_key(0xA, false);
+
+    assert!(!machine.cpu.keypad.keys[0xA]);
+
+    machine.set_key(0x10, true);
+    assert!(machine.cpu.keypad.keys.iter().all(|&key| !key));
+}
+
+#[test]
+fn test_framebuffer() {
+    // I = font digit 0, draw it at (0, 0)
+    let mut machine = build_machine(&[0xA0, 0x00, 0xD0, 0x05]);
+    machine.step().unwrap();
+    machine.step().unwrap();
+
+    let display = machine.framebuffer();
+    assert_eq!(display.width(), 64);
+    assert_eq!(display.memory[0][0..4], [1, 1, 1, 1]);
+    assert_eq!(display.memory[1][0..4], [1, 0, 0, 1]);
+}
+
+#[test]
+fn test_vip_timing() {
+    // a jump to itself costs 52 cycles
+    let mut machine = build_machine(&[0x12, 0x00]);
+    machine.timing = Timing::Vip;
+    machine.run_frame().unwrap();
+    // the 50th jump runs past the budget...
+    assert_eq!(machine.cpu.instructions, 50);
+    assert_eq!(machine.cpu.vip_cycles, 2600);
+    // ...and the overshoot comes out of the next frame
+    machine.run_frame().unwrap();
+    assert_eq!(machine.cpu.instructions, 99);
+}
+
+#[test]
+fn test_vip_timing_slow_instructions() {
+    // clearing the screen takes most of a frame on the VIP
+    let mut machine = build_machine(&[0x00, 0xE0, 0x12, 0x00]);
+    machine.timing = Timing::Vip;
+    machine.run_frame().unwrap();
+    assert_eq!(machine.cpu.instructions, 1);
+}
+
+#[test]
+fn test_timing_parse() {
+    assert_eq!("vip".parse(), Ok(Timing::Vip));
+    assert_eq!(Timing::Ips.to_string(), "ips");
+    assert!("cycles".parse::<Timing>().is_err());
+}
+
+#[test]
+fn test_display_wait_one_draw_a_frame() {
+    // draw, add one to V0, loop
+    let mut machine = build_machine(&[0xD0, 0x01, 0x70, 0x01, 0x12, 0x00]);
+    machine.run_frame().unwrap();
+    assert_eq!(machine.cpu.v[0], 0);
+    machine.run_frame().unwrap();
+    machine.run_frame().unwrap();
+    assert_eq!(machine.cpu.v[0], 2);
+
+    let mut machine = build_machine(&[0xD0, 0x01, 0x70, 0x01, 0x12, 0x00]);
+    machine.cpu.quirks.display_wait = false;
+    machine.run_frame().unwrap();
+    assert!(machine.cpu.v[0] > 1);
+}
+
+#[test]
+fn test_key_wait_ends_the_frame() {
+    // wait for a key into V2, V0 = 1, loop
+    let mut machine = build_machine(&[0xF2, 0x0A, 0x60, 0x01, 0x12, 0x04]);
+    machine.run_frame().unwrap();
+    assert!(machine.waiting_for_key());
+    assert_eq!(machine.cpu.instructions, 1);
+
+    // a tap between two frames still counts
+    machine.set_key(0xB, true);
+    machine.set_key(0xB, false);
+    assert!(!machine.waiting_for_key());
+    machine.run_frame().unwrap();
+    assert_eq!((machine.cpu.v[0], machine.cpu.v[2]), (1, 0xB));
+}
+
+#[test]
+fn test_audio_pattern() {
+    let mut machine = Machine::new(Platform::XoChip, Quirks::default());
+    // I = 0x208, load the pattern, pitch = V0 = 80
+    machine.load_rom(&[0xA2, 0x08, 0xF0, 0x02, 0x60, 0x50, 0xF0, 0x3A, 0xAA, 0x55]);
+    for _ in 0..4 {
+        machine.step().unwrap();
+    }
+    let (bits, pitch) = machine.audio_pattern().unwrap();
+    assert_eq!((&bits[..2], pitch), (&[0xAA, 0x55][..], 80));
+
+    assert_eq!(build_machine(&[]).audio_pattern(), None);
+}
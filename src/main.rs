@@ -1,135 +1,416 @@
-use std::{env, time::Duration};
-
-use sdl3::{event::Event, keyboard::Keycode, pixels::Color, rect::Rect};
-
-mod cpu;
-mod display;
-mod keypad;
-
-const SCALE: u32 = 15;
-const WINDOW_WIDTH: u32 = (display::WIDTH as u32) * SCALE;
-const WINDOW_HEIGHT: u32 = (display::HEIGHT as u32) * SCALE;
-
-const INSTRUCTIONS_PER_SECOND: u32 = 1000;
-const TIMER_FREQUENCY: u32 = 60;
-const CYCLES_PER_FRAME: u32 = INSTRUCTIONS_PER_SECOND / TIMER_FREQUENCY;
-
-fn handle_key_event(keycode: Keycode, pressed: bool, cpu: &mut cpu::Cpu) {
-    let key = match keycode {
-        Keycode::_1 => Some(0x1),
-        Keycode::_2 => Some(0x2),
-        Keycode::_3 => Some(0x3),
-        Keycode::_4 => Some(0xC),
-        Keycode::Q => Some(0x4),
-        Keycode::W => Some(0x5),
-        Keycode::E => Some(0x6),
-        Keycode::R => Some(0xD),
-        Keycode::A => Some(0x7),
-        Keycode::S => Some(0x8),
-        Keycode::D => Some(0x9),
-        Keycode::F => Some(0xE),
-        Keycode::Z => Some(0xA),
-        Keycode::X => Some(0x0),
-        Keycode::C => Some(0xB),
-        Keycode::V => Some(0xF),
-        _ => None,
-    };
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, Subcommand};
+use marisa_rs::{
+    assembler,
+    audio::Waveform,
+    config::Config,
+    disasm, hash_rom,
+    instruction::Syntax,
+    keymap::Keymap,
+    palette::Palette,
+    persistence::Persistence,
+    quirks,
+    render::Scaling,
+    rng::RngMode,
+    romdb::{RomDb, RomSettings},
+    Machine, Timing, INSTRUCTIONS_PER_SECOND,
+};
+
+mod frontend;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "a CHIP-8 emulator",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Args {
+    #[command(subcommand)]
+    tool: Option<Tool>,
+
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Tool {
+    /// run a ROM, same as leaving out the subcommand
+    Run(Box<RunArgs>),
+    /// print the disassembly of a ROM
+    Disasm {
+        rom_path: PathBuf,
+
+        /// mnemonic syntax: octo or cowgod
+        #[arg(long, default_value_t = Syntax::Octo)]
+        syntax: Syntax,
+    },
+    /// assemble Octo source into a ROM
+    Asm {
+        source_path: PathBuf,
+
+        /// where to write the ROM
+        #[arg(short, long, default_value = "out.ch8")]
+        output: PathBuf,
+
+        /// also write the label addresses to this file
+        #[arg(long)]
+        symbols: Option<PathBuf>,
+    },
+}
+
+#[derive(clap::Args)]
+struct RunArgs {
+    #[arg(required = true)]
+    rom_path: Option<PathBuf>,
+
+    /// interpreter to emulate: vip, chip48, schip, schip-legacy or xochip; defaults to the ROM database's, then vip
+    #[arg(long)]
+    profile: Option<quirks::Profile>,
+
+    /// 8XY6/8XYE shift VY instead of VX
+    #[arg(long, value_name = "BOOL")]
+    shift_uses_vy: Option<bool>,
+
+    /// FX55/FX65 increment I
+    #[arg(long, value_name = "BOOL")]
+    increment_i: Option<bool>,
+
+    /// FX55/FX65 increment I by X instead of X + 1, when they increment it at all
+    #[arg(long, value_name = "BOOL")]
+    increment_i_by_x: Option<bool>,
+
+    /// 8XY1/8XY2/8XY3 reset VF
+    #[arg(long, value_name = "BOOL")]
+    vf_reset: Option<bool>,
+
+    /// BNNN jumps to XNN + VX instead of NNN + V0
+    #[arg(long, value_name = "BOOL")]
+    jump_uses_vx: Option<bool>,
+
+    /// DXYN clips sprites at the screen edge instead of wrapping
+    #[arg(long, value_name = "BOOL")]
+    clip_sprites: Option<bool>,
+
+    /// DXYN waits for the next frame before the ROM carries on
+    #[arg(long, value_name = "BOOL")]
+    display_wait: Option<bool>,
+
+    /// beep frequency in Hz
+    #[arg(long, default_value_t = 440.0)]
+    beep_frequency: f32,
+
+    /// beep volume, from 0.0 to 1.0
+    #[arg(long, default_value_t = 0.25)]
+    beep_volume: f32,
+
+    /// beep waveform: square, sine or triangle
+    #[arg(long, default_value_t = Waveform::Square)]
+    beep_waveform: Waveform,
+
+    /// start paused with the debugger reading commands from stdin
+    #[arg(long)]
+    debug: bool,
+
+    /// seed for CXKK's random numbers, picked at random when left out
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// random number generator: seeded, or vip for the COSMAC VIP's routine
+    #[arg(long, default_value_t = RngMode::Seeded)]
+    rng: RngMode,
+
+    /// record keypad input to a movie file
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["play", "debug"])]
+    record: Option<PathBuf>,
+
+    /// play back a movie file, which also sets the profile, quirks, seed, speed and timing
+    #[arg(long, value_name = "PATH", conflicts_with = "debug")]
+    play: Option<PathBuf>,
+
+    /// frames between rewind snapshots
+    #[arg(long, default_value_t = 2)]
+    rewind_interval: u32,
+
+    /// seconds of rewind history to keep
+    #[arg(long, default_value_t = 120)]
+    rewind_seconds: u32,
+
+    /// run without a window or audio, for CI and scripting
+    #[arg(long, conflicts_with_all = ["debug", "record", "play"])]
+    headless: bool,
+
+    /// frames to run headless, fewer if the ROM exits
+    #[arg(long, default_value_t = 600, requires = "headless")]
+    frames: u64,
+
+    /// key script for headless runs, lines of `<frame> <key> down|up`
+    #[arg(long, value_name = "PATH", requires = "headless")]
+    keys: Option<PathBuf>,
+
+    /// write the final screen to a .png, .pbm or .txt file, or `-` for ASCII art on stdout
+    #[arg(long, value_name = "PATH", requires = "headless")]
+    screenshot: Option<PathBuf>,
+
+    /// window scaling: integer keeps every pixel the same size, fit fills as much as it can
+    #[arg(long, default_value_t = Scaling::Integer)]
+    scaling: Scaling,
+
+    /// color palette: a name (classic, amber, green, lcd, octo, high-contrast, colorblind) or 2 to 4 hex colors for off, plane 1, plane 2 and both planes, e.g. `#000000,#33ff66`; F10 cycles through the named ones
+    #[arg(long)]
+    palette: Option<Palette>,
+
+    /// CPU speed in instructions per second, independent of the 60 Hz timers; defaults to the ROM database's, then 1000
+    #[arg(long, value_parser = clap::value_parser!(u32).range(1..))]
+    ips: Option<u32>,
+
+    /// ips runs --ips instructions a second, vip runs the COSMAC VIP's cycle budget each frame with every instruction costing what it did on the original interpreter
+    #[arg(long, default_value_t = Timing::Ips)]
+    timing: Timing,
+
+    /// anti-flicker smoothing: off, fade[:decay] (default 0.5 per frame), max[:frames] (default 2) or vblank, which holds back frames that only erased
+    #[arg(long, default_value_t = Persistence::Off)]
+    persistence: Persistence,
+
+    /// start fullscreen, F11 toggles it
+    #[arg(long)]
+    fullscreen: bool,
+
+    /// config file, defaults to ~/.config/marisa-rs/config.toml; a .toml next to the ROM is read after it
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// ROM settings database, defaults to ~/.config/marisa-rs/roms.toml; its entries replace the built-in ones
+    #[arg(long, value_name = "PATH")]
+    rom_db: Option<PathBuf>,
 
-    if let Some(key) = key {
-        if pressed {
-            cpu.keypad.key_down(key);
+    /// print the key bindings the ROM would run with and exit
+    #[arg(long)]
+    print_keymap: bool,
+}
+
+impl RunArgs {
+    fn profile(&self) -> quirks::Profile {
+        self.profile.unwrap_or(quirks::Profile::CosmacVip)
+    }
+
+    fn ips(&self) -> u32 {
+        self.ips.unwrap_or(INSTRUCTIONS_PER_SECOND)
+    }
+
+    // prints a status line, on stderr when stdout carries an ASCII screenshot
+    // so the picture can be piped on its own
+    fn status(&self, message: std::fmt::Arguments) {
+        if self
+            .screenshot
+            .as_ref()
+            .is_some_and(|path| path.as_os_str() == "-")
+        {
+            eprintln!("{}", message);
         } else {
-            cpu.keypad.key_up(key);
+            println!("{}", message);
         }
     }
-}
 
-fn main() -> Result<(), anyhow::Error> {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: {} <rom_path>", args[0]);
-        std::process::exit(1);
+    // fills in what the ROM database knows and the command line left out
+    fn apply_rom_settings(&mut self, settings: &RomSettings) {
+        self.profile = self.profile.or(settings.profile);
+        self.ips = self.ips.or(settings.ips);
     }
 
-    let sdl_context = sdl3::init()?;
-    let video_subsystem = sdl_context.video()?;
-
-    let window = video_subsystem
-        .window("marisa-rs v2025.4.0-alpha", WINDOW_WIDTH, WINDOW_HEIGHT)
-        .position_centered()
-        .build()?;
+    fn quirks(&self) -> quirks::Quirks {
+        let mut quirks = self.profile().quirks();
+        if let Some(value) = self.shift_uses_vy {
+            quirks.shift_uses_vy = value;
+        }
+        if let Some(value) = self.increment_i {
+            quirks.increment_i = value;
+        }
+        if let Some(value) = self.increment_i_by_x {
+            quirks.increment_i_by_x = value;
+        }
+        if let Some(value) = self.vf_reset {
+            quirks.vf_reset = value;
+        }
+        if let Some(value) = self.jump_uses_vx {
+            quirks.jump_uses_vx = value;
+        }
+        if let Some(value) = self.clip_sprites {
+            quirks.clip_sprites = value;
+        }
+        if let Some(value) = self.display_wait {
+            quirks.display_wait = value;
+        }
+        quirks
+    }
 
-    let mut canvas = window.into_canvas();
+    // a machine with the ROM loaded and the seed it was given
+    fn machine(&self, rom_data: &[u8]) -> (Machine, u64) {
+        let mut machine = Machine::new(self.profile().platform(), self.quirks());
+        // printed so a run can be reproduced with --seed
+        let seed = self.seed.unwrap_or_else(rand::random);
+        self.status(format_args!("RNG seed: {}", seed));
+        machine.cpu.rng = self.rng.create(seed);
+        machine.ips = self.ips();
+        machine.timing = self.timing;
+        machine.load_rom(rom_data);
+        (machine, seed)
+    }
 
-    let mut cpu = cpu::Cpu::new();
-    cpu.reset();
+    // the global config, the ROM database's entry, then the ROM's own config,
+    // whichever exist; an explicit --config has to exist
+    fn configs(
+        &self,
+        rom_path: &Path,
+        rom_settings: Option<&RomSettings>,
+    ) -> Result<Vec<(PathBuf, Config)>, anyhow::Error> {
+        let global = match &self.config {
+            Some(path) => Some((path.clone(), true)),
+            None => config_path("config.toml").map(|path| (path, false)),
+        };
 
-    if let Some(rom_path) = std::env::args().nth(1) {
-        let rom_data = std::fs::read(rom_path)?;
-        cpu.load(&rom_data);
+        let mut configs = Vec::new();
+        if let Some((path, required)) = global {
+            if let Some(config) = read_file(&path, required)? {
+                configs.push((path, config));
+            }
+        }
+        if let Some(settings) = rom_settings {
+            configs.push((PathBuf::from("ROM database"), settings.config.clone()));
+        }
+        let path = rom_path.with_extension("toml");
+        if let Some(config) = read_file(&path, false)? {
+            configs.push((path, config));
+        }
+        Ok(configs)
     }
 
-    let mut event_pump = sdl_context.event_pump()?;
-
-    'running: loop {
-        let frame_start = std::time::Instant::now();
-
-        while let Some(event) = event_pump.poll_event() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => break 'running,
-                Event::KeyUp {
-                    keycode: Some(key), ..
-                } => {
-                    println!("Key up: {:?}", key); // Debug print
-                    handle_key_event(key, false, &mut cpu);
-                }
-                Event::KeyDown {
-                    keycode: Some(key), ..
-                } => {
-                    println!("Key down: {:?}", key); // Debug print
-                    handle_key_event(key, true, &mut cpu);
-                }
-                _ => {}
+    // the built-in database with the user's entries on top; an explicit --rom-db has to exist
+    fn rom_db(&self) -> Result<RomDb, anyhow::Error> {
+        let mut db = RomDb::builtin();
+        let user = match &self.rom_db {
+            Some(path) => Some((path.clone(), true)),
+            None => config_path("roms.toml").map(|path| (path, false)),
+        };
+        if let Some((path, required)) = user {
+            if let Some(user) = read_file(&path, required)? {
+                db.extend(user);
             }
         }
+        Ok(db)
+    }
+}
+
+// $XDG_CONFIG_HOME/marisa-rs/<name>, falling back to ~/.config
+fn config_path(name: &str) -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".config")))?;
+    Some(config_home.join("marisa-rs").join(name))
+}
+
+// parses a TOML file, None when it's missing and doesn't have to be there
+fn read_file<T>(path: &Path, required: bool) -> Result<Option<T>, anyhow::Error>
+where
+    T: std::str::FromStr<Err = String>,
+{
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && !required => return Ok(None),
+        Err(err) => return Err(anyhow::anyhow!("{}: {}", path.display(), err)),
+    };
+    text.parse()
+        .map(Some)
+        .map_err(|err| anyhow::anyhow!("{}: {}", path.display(), err))
+}
+
+fn keymap(configs: &[(PathBuf, Config)]) -> Keymap {
+    let mut keymap = Keymap::default();
+    for (_, config) in configs {
+        keymap.apply(config);
+    }
+    keymap
+}
 
-        for _ in 0..CYCLES_PER_FRAME {
-            cpu.execute();
-        }
-        cpu.decrement_timers();
-
-        if cpu.display.draw_flag {
-            canvas.set_draw_color(Color::RGB(0, 0, 0));
-            canvas.clear();
-
-            canvas.set_draw_color(Color::RGB(255, 255, 255));
-            for y in 0..display::HEIGHT {
-                for x in 0..display::WIDTH {
-                    if cpu.display.memory[y][x] == 1 {
-                        let rect = Rect::new(
-                            (x as u32 * SCALE) as i32,
-                            (y as u32 * SCALE) as i32,
-                            SCALE,
-                            SCALE,
-                        );
-                        canvas.fill_rect(rect)?;
-                    }
-                }
+// --palette, then the last config that sets one
+#[cfg(feature = "sdl")]
+fn palette(run: &RunArgs, configs: &[(PathBuf, Config)]) -> Palette {
+    if let Some(palette) = &run.palette {
+        return palette.clone();
+    }
+    configs
+        .iter()
+        .rev()
+        .find_map(|(_, config)| config.palette.as_ref())
+        // checked when the config was parsed
+        .map_or_else(Palette::default, |palette| palette.parse().unwrap())
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let args = Args::parse();
+
+    let mut run = match args.tool {
+        Some(Tool::Disasm { rom_path, syntax }) => {
+            let rom_data = std::fs::read(rom_path)?;
+            println!(
+                "{}",
+                disasm::disassemble(&rom_data, disasm::ROM_START, syntax)
+            );
+            return Ok(());
+        }
+        Some(Tool::Asm {
+            source_path,
+            output,
+            symbols,
+        }) => {
+            let source = std::fs::read_to_string(&source_path)?;
+            let program = assembler::assemble(&source)
+                .map_err(|err| anyhow::anyhow!("{}: {}", source_path.display(), err))?;
+            std::fs::write(output, &program.rom)?;
+            if let Some(symbols) = symbols {
+                std::fs::write(symbols, program.symbol_map())?;
             }
+            return Ok(());
+        }
+        Some(Tool::Run(run)) => *run,
+        None => args.run,
+    };
+    // clap requires the rom path whenever there's no other subcommand
+    let rom_path = run.rom_path.clone().expect("rom path is required");
+    let rom_data = std::fs::read(&rom_path)?;
 
-            canvas.present();
-            cpu.display.draw_flag = false;
+    let rom_db = run.rom_db()?;
+    let rom_settings = rom_db.get(&hash_rom(&rom_data));
+    if let Some(settings) = rom_settings {
+        if let Some(title) = &settings.title {
+            run.status(format_args!("ROM: {}", title));
         }
+        run.apply_rom_settings(settings);
+    }
+    let configs = run.configs(&rom_path, rom_settings)?;
 
-        let frame_time = frame_start.elapsed();
-        if frame_time < Duration::from_millis(16) {
-            std::thread::sleep(Duration::from_millis(16) - frame_time);
+    if run.print_keymap {
+        for (path, _) in &configs {
+            println!("# {}", path.display());
         }
+        print!("{}", keymap(&configs));
+        return Ok(());
     }
 
-    Ok(())
+    if run.headless {
+        return frontend::headless::run(&run, &rom_data);
+    }
+    #[cfg(feature = "sdl")]
+    return frontend::sdl::run(
+        &run,
+        &rom_path,
+        &rom_data,
+        &keymap(&configs),
+        palette(&run, &configs),
+    );
+    #[cfg(not(feature = "sdl"))]
+    Err(anyhow::anyhow!(
+        "built without the `sdl` feature, only `run --headless` is available"
+    ))
 }
@@ -0,0 +1,307 @@
+use std::{fmt, str::FromStr};
+
+use sha1::{Digest, Sha1};
+
+use crate::{
+    cpu::CpuError,
+    hash_rom, headless, hex,
+    quirks::{Profile, Quirks},
+    rng::RngMode,
+    unhex, Machine, RomHash, Timing,
+};
+
+const HEADER: &str = "marisa-movie 1";
+
+// a keypad transition, applied before the frame with this number runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputEvent {
+    pub frame: u64,
+    pub key: u8,
+    pub pressed: bool,
+}
+
+// everything needed to replay a session: the machine setup and its input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Movie {
+    pub rom_hash: RomHash,
+    pub seed: u64,
+    pub rng: RngMode,
+    pub profile: Profile,
+    pub quirks: Quirks,
+    pub ips: u32,
+    pub timing: Timing,
+    // number of frames recorded
+    pub frames: u64,
+    // hash of the save state after the last frame
+    pub final_state: [u8; 20],
+    pub events: Vec<InputEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MovieError {
+    RomMismatch,
+    Cpu(CpuError),
+    // the replay ended in a different state than the recording
+    Desync,
+}
+
+impl fmt::Display for MovieError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MovieError::RomMismatch => write!(f, "movie was recorded with a different ROM"),
+            MovieError::Cpu(err) => write!(f, "{}", err),
+            MovieError::Desync => write!(f, "replay ended in a different state than the recording"),
+        }
+    }
+}
+
+impl std::error::Error for MovieError {}
+
+impl From<CpuError> for MovieError {
+    fn from(err: CpuError) -> MovieError {
+        MovieError::Cpu(err)
+    }
+}
+
+pub fn state_hash(machine: &Machine) -> [u8; 20] {
+    Sha1::digest(machine.save_state()).into()
+}
+
+impl Movie {
+    // a machine set up the way the recording's was, with the ROM loaded
+    pub fn machine(&self, rom: &[u8]) -> Result<Machine, MovieError> {
+        if hash_rom(rom) != self.rom_hash {
+            return Err(MovieError::RomMismatch);
+        }
+        let mut machine = Machine::new(self.profile.platform(), self.quirks);
+        machine.cpu.rng = self.rng.create(self.seed);
+        machine.ips = self.ips;
+        machine.timing = self.timing;
+        machine.load_rom(rom);
+        Ok(machine)
+    }
+
+    // replays the whole movie and checks where it ended up
+    pub fn play(&self, rom: &[u8]) -> Result<Machine, MovieError> {
+        let mut machine = self.machine(rom)?;
+        let mut player = Player::new(self.clone());
+        while !player.is_finished() {
+            player.apply(&mut machine);
+            machine.run_frame()?;
+            player.end_frame();
+        }
+        player.verify(&machine)?;
+        Ok(machine)
+    }
+}
+
+pub struct Recorder {
+    movie: Movie,
+}
+
+impl Recorder {
+    // call right after the machine is set up and the ROM is loaded
+    pub fn new(machine: &Machine, seed: u64, profile: Profile) -> Recorder {
+        Recorder {
+            movie: Movie {
+                rom_hash: machine.rom_hash(),
+                seed,
+                rng: machine.cpu.rng.save().0,
+                profile,
+                quirks: machine.cpu.quirks,
+                ips: machine.ips,
+                timing: machine.timing,
+                frames: 0,
+                final_state: [0; 20],
+                events: Vec::new(),
+            },
+        }
+    }
+
+    // presses or releases a key, recording it if that changed anything; keys
+    // past F are ignored like Machine::set_key does
+    pub fn set_key(&mut self, machine: &mut Machine, key: usize, pressed: bool) {
+        if machine.cpu.keypad.keys.get(key) != Some(&!pressed) {
+            return;
+        }
+        machine.set_key(key, pressed);
+        self.movie.events.push(InputEvent {
+            frame: self.movie.frames,
+            key: key as u8,
+            pressed,
+        });
+    }
+
+    pub fn end_frame(&mut self) {
+        self.movie.frames += 1;
+    }
+
+    pub fn finish(mut self, machine: &Machine) -> Movie {
+        self.movie.final_state = state_hash(machine);
+        self.movie
+    }
+}
+
+pub struct Player {
+    movie: Movie,
+    frame: u64,
+    next_event: usize,
+}
+
+impl Player {
+    pub fn new(movie: Movie) -> Player {
+        Player {
+            movie,
+            frame: 0,
+            next_event: 0,
+        }
+    }
+
+    // feeds the events for the frame about to run
+    pub fn apply(&mut self, machine: &mut Machine) {
+        while let Some(event) = self.movie.events.get(self.next_event) {
+            if event.frame > self.frame {
+                break;
+            }
+            machine.set_key(event.key as usize, event.pressed);
+            self.next_event += 1;
+        }
+    }
+
+    pub fn end_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.movie.frames
+    }
+
+    pub fn verify(&self, machine: &Machine) -> Result<(), MovieError> {
+        if state_hash(machine) != self.movie.final_state {
+            return Err(MovieError::Desync);
+        }
+        Ok(())
+    }
+}
+
+// a line based text format, so movies can be read and diffed:
+//   marisa-movie 1
+//   rom <sha1>
+//   seed 1234
+//   rng seeded
+//   profile vip
+//   quirk shift-uses-vy true
+//   ips 1000
+//   timing ips
+//   frames 600
+//   final <sha1>
+//   key 12 a down
+impl fmt::Display for Movie {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "rom {}", hex(&self.rom_hash))?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "rng {}", self.rng)?;
+        writeln!(f, "profile {}", self.profile)?;
+        for (name, value) in self.quirks.flags() {
+            writeln!(f, "quirk {} {}", name, value)?;
+        }
+        writeln!(f, "ips {}", self.ips)?;
+        writeln!(f, "timing {}", self.timing)?;
+        writeln!(f, "frames {}", self.frames)?;
+        writeln!(f, "final {}", hex(&self.final_state))?;
+        for event in &self.events {
+            let action = if event.pressed { "down" } else { "up" };
+            writeln!(f, "key {} {:x} {}", event.frame, event.key, action)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Movie {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Movie, String> {
+        let mut lines = s.lines().enumerate();
+        if lines.next().map(|(_, line)| line.trim()) != Some(HEADER) {
+            return Err("not a movie file".to_string());
+        }
+
+        let (mut rom_hash, mut final_state) = (None, None);
+        let (mut seed, mut rng, mut profile, mut frames) = (None, None, None, None);
+        let (mut ips, mut timing) = (None, None);
+        let mut quirks = Vec::new();
+        let mut events: Vec<InputEvent> = Vec::new();
+
+        for (index, line) in lines {
+            let error = |message: String| format!("line {}: {}", index + 1, message);
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => {}
+                ["rom", hash] => rom_hash = Some(unhex(hash).map_err(error)?),
+                ["seed", value] => {
+                    seed = Some(value.parse().map_err(|_| error("invalid seed".into()))?)
+                }
+                ["rng", mode] => rng = Some(mode.parse().map_err(error)?),
+                ["profile", name] => profile = Some(name.parse::<Profile>().map_err(error)?),
+                ["quirk", name, value] => {
+                    let value = value
+                        .parse()
+                        .map_err(|_| error(format!("invalid value for `{}`", name)))?;
+                    quirks.push((index, name.to_string(), value));
+                }
+                ["ips", value] => {
+                    ips = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|&ips| ips > 0)
+                            .ok_or_else(|| error("invalid ips".into()))?,
+                    )
+                }
+                ["timing", value] => timing = Some(value.parse().map_err(error)?),
+                ["frames", value] => {
+                    frames = Some(value.parse().map_err(|_| error("invalid frames".into()))?)
+                }
+                ["final", hash] => final_state = Some(unhex(hash).map_err(error)?),
+                ["key", event @ ..] => {
+                    events.push(headless::parse_event(event, &events).map_err(error)?)
+                }
+                _ => return Err(error(format!("unrecognized line `{}`", line.trim()))),
+            }
+        }
+
+        let missing = |field: &str| format!("missing `{}`", field);
+        let profile = profile.ok_or_else(|| missing("profile"))?;
+        // every quirk is written out, so a replay never depends on what the
+        // profile's defaults happen to be now
+        let mut movie_quirks = profile.quirks();
+        for (index, name, value) in &quirks {
+            movie_quirks
+                .set(name, *value)
+                .map_err(|err| format!("line {}: {}", index + 1, err))?;
+        }
+        for (name, _) in movie_quirks.flags() {
+            if !quirks.iter().any(|(_, quirk, _)| quirk == name) {
+                return Err(missing(&format!("quirk {}", name)));
+            }
+        }
+
+        Ok(Movie {
+            rom_hash: rom_hash.ok_or_else(|| missing("rom"))?,
+            seed: seed.ok_or_else(|| missing("seed"))?,
+            rng: rng.ok_or_else(|| missing("rng"))?,
+            profile,
+            quirks: movie_quirks,
+            ips: ips.ok_or_else(|| missing("ips"))?,
+            timing: timing.ok_or_else(|| missing("timing"))?,
+            frames: frames.ok_or_else(|| missing("frames"))?,
+            final_state: final_state.ok_or_else(|| missing("final"))?,
+            events,
+        })
+    }
+}
+
+#[cfg(test)]
+#[path = "./movie_tests.rs"]
+mod movie_tests;
@@ -0,0 +1,167 @@
+use super::*;
+use crate::assembler::assemble;
+
+// counts instructions with key 0 held and keeps drawing random numbers
+fn rom() -> Vec<u8> {
+    assemble(
+        "
+        loop
+            if v3 key then v4 += 1
+            v5 := random 0xFF
+        again
+        ",
+    )
+    .unwrap()
+    .rom
+}
+
+fn record() -> Movie {
+    let profile = Profile::CosmacVip;
+    let mut machine = Machine::new(profile.platform(), profile.quirks());
+    machine.cpu.rng = RngMode::Seeded.create(99);
+    machine.load_rom(&rom());
+
+    let mut recorder = Recorder::new(&machine, 99, profile);
+    for frame in 0..10 {
+        match frame {
+            2 => recorder.set_key(&mut machine, 0, true),
+            // holding a key that's already down isn't a transition
+            3 => recorder.set_key(&mut machine, 0, true),
+            5 => recorder.set_key(&mut machine, 0, false),
+            _ => {}
+        }
+        machine.run_frame().unwrap();
+        recorder.end_frame();
+    }
+    recorder.finish(&machine)
+}
+
+#[test]
+fn test_record() {
+    let movie = record();
+
+    assert_eq!(movie.frames, 10);
+    assert_eq!(
+        movie.events,
+        [
+            InputEvent {
+                frame: 2,
+                key: 0,
+                pressed: true
+            },
+            InputEvent {
+                frame: 5,
+                key: 0,
+                pressed: false
+            },
+        ]
+    );
+    assert_eq!(movie.rom_hash, hash_rom(&rom()));
+}
+
+#[test]
+fn test_play() {
+    let movie = record();
+    let machine = movie.play(&rom()).unwrap();
+
+    assert!(machine.cpu.v[4] > 0);
+    assert!(!machine.cpu.keypad.keys[0]);
+}
+
+#[test]
+fn test_desync() {
+    let mut movie = record();
+    movie.events[1].frame = 6;
+
+    assert_eq!(movie.play(&rom()).err(), Some(MovieError::Desync));
+
+    let mut movie = record();
+    movie.seed = 100;
+    assert_eq!(movie.play(&rom()).err(), Some(MovieError::Desync));
+}
+
+#[test]
+fn test_rom_mismatch() {
+    assert_eq!(
+        record().play(&[0x12, 0x00]).err(),
+        Some(MovieError::RomMismatch)
+    );
+}
+
+#[test]
+fn test_text_round_trip() {
+    let mut movie = record();
+    movie.quirks.clip_sprites = false;
+    let text = movie.to_string();
+
+    assert!(text.starts_with("marisa-movie 1\nrom "));
+    assert!(text.contains("\nquirk clip-sprites false\n"));
+    assert!(text.ends_with("\nkey 2 0 down\nkey 5 0 up\n"));
+    assert_eq!(text.parse::<Movie>(), Ok(movie));
+}
+
+#[test]
+fn test_parse_errors() {
+    let text = record().to_string();
+
+    assert_eq!(
+        "hello".parse::<Movie>(),
+        Err("not a movie file".to_string())
+    );
+    assert_eq!(
+        text.replace("profile vip\n", "").parse::<Movie>(),
+        Err("missing `profile`".to_string())
+    );
+    assert_eq!(
+        format!("{}key 1 0 up\n", text).parse::<Movie>(),
+        Err("line 19: key events are out of order".to_string())
+    );
+    assert_eq!(
+        text.replace("key 2 0 down", "key 2 g down")
+            .parse::<Movie>(),
+        Err("line 17: invalid key `g`".to_string())
+    );
+}
+
+#[test]
+fn test_ips() {
+    let mut movie = record();
+    movie.ips = 1500;
+    let text = movie.to_string();
+    assert!(text.contains("\nips 1500\n"));
+    assert_eq!(movie.machine(&rom()).unwrap().ips, 1500);
+
+    assert_eq!(
+        text.replace("ips 1500\n", "").parse::<Movie>(),
+        Err("missing `ips`".to_string())
+    );
+    assert!(text.replace("ips 1500", "ips 0").parse::<Movie>().is_err());
+}
+
+#[test]
+fn test_timing() {
+    let mut movie = record();
+    movie.timing = Timing::Vip;
+    let text = movie.to_string();
+    assert!(text.contains("\ntiming vip\n"));
+    assert_eq!(movie.machine(&rom()).unwrap().timing, Timing::Vip);
+
+    assert_eq!(
+        text.replace("timing vip\n", "").parse::<Movie>(),
+        Err("missing `timing`".to_string())
+    );
+}
+
+#[test]
+fn test_every_quirk_is_required() {
+    let text = record().to_string();
+    assert!(text.contains("\nquirk display-wait true\n"));
+    assert_eq!(
+        text.replace("quirk display-wait true\n", "")
+            .parse::<Movie>(),
+        Err("missing `quirk display-wait`".to_string())
+    );
+    // a quirk set against the profile's default comes back the same way
+    let text = text.replace("quirk vf-reset true", "quirk vf-reset false");
+    assert!(!text.parse::<Movie>().unwrap().quirks.vf_reset);
+}
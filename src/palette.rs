@@ -0,0 +1,192 @@
+use std::{fmt, str::FromStr};
+
+pub type Rgb = [u8; 3];
+
+// colors indexed by the bitplanes set in a framebuffer cell: background, plane
+// 1, plane 2, both planes. single-plane games only use the first two
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette {
+    // the name it was picked by, "custom" for hex colors
+    pub name: String,
+    pub colors: [Rgb; 4],
+}
+
+pub const NAMED: [(&str, [Rgb; 4]); 7] = [
+    (
+        "classic",
+        [
+            [0x00, 0x00, 0x00],
+            [0xFF, 0xFF, 0xFF],
+            [0xAA, 0xAA, 0xAA],
+            [0x55, 0x55, 0x55],
+        ],
+    ),
+    (
+        "amber",
+        [
+            [0x1A, 0x10, 0x00],
+            [0xFF, 0xB0, 0x00],
+            [0xB3, 0x6B, 0x00],
+            [0x66, 0x3D, 0x00],
+        ],
+    ),
+    (
+        "green",
+        [
+            [0x05, 0x14, 0x08],
+            [0x33, 0xFF, 0x66],
+            [0x22, 0xAA, 0x44],
+            [0x11, 0x55, 0x22],
+        ],
+    ),
+    (
+        "lcd",
+        [
+            [0x9B, 0xBC, 0x0F],
+            [0x30, 0x62, 0x30],
+            [0x6B, 0x8E, 0x23],
+            [0x0F, 0x38, 0x0F],
+        ],
+    ),
+    (
+        "octo",
+        [
+            [0x99, 0x66, 0x00],
+            [0xFF, 0xCC, 0x00],
+            [0xFF, 0x66, 0x00],
+            [0x66, 0x22, 0x00],
+        ],
+    ),
+    (
+        "high-contrast",
+        [
+            [0x00, 0x00, 0x00],
+            [0xFF, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0x00],
+            [0x00, 0xFF, 0xFF],
+        ],
+    ),
+    // from the Okabe-Ito set, which stays distinct under the common kinds of color blindness
+    (
+        "colorblind",
+        [
+            [0x00, 0x00, 0x00],
+            [0xFF, 0xFF, 0xFF],
+            [0xE6, 0x9F, 0x00],
+            [0x56, 0xB4, 0xE9],
+        ],
+    ),
+];
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette::named(NAMED[0].0).unwrap()
+    }
+}
+
+impl Palette {
+    pub fn named(name: &str) -> Option<Palette> {
+        NAMED
+            .iter()
+            .find(|(named, _)| *named == name)
+            .map(|&(name, colors)| Palette {
+                name: name.to_string(),
+                colors,
+            })
+    }
+
+    // the named palette after this one, wrapping around; custom palettes go back to the first
+    pub fn next(&self) -> Palette {
+        let index = NAMED
+            .iter()
+            .position(|(name, _)| *name == self.name)
+            .map_or(0, |index| (index + 1) % NAMED.len());
+        Palette::named(NAMED[index].0).unwrap()
+    }
+}
+
+impl fmt::Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if Palette::named(&self.name).is_some() {
+            return write!(f, "{}", self.name);
+        }
+        let colors: Vec<String> = self
+            .colors
+            .iter()
+            .map(|[r, g, b]| format!("#{:02x}{:02x}{:02x}", r, g, b))
+            .collect();
+        write!(f, "{}", colors.join(","))
+    }
+}
+
+// a palette name, or two to four comma separated hex colors; the plane 2 and
+// both-planes colors default to shades between the first two
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Palette, String> {
+        if let Some(palette) = Palette::named(s) {
+            return Ok(palette);
+        }
+        if !s.contains('#') && !s.contains(',') {
+            let names: Vec<&str> = NAMED.iter().map(|(name, _)| *name).collect();
+            return Err(format!(
+                "unknown palette `{}` (expected {} or hex colors)",
+                s,
+                names.join(", ")
+            ));
+        }
+
+        let colors = s
+            .split(',')
+            .map(|color| parse_color(color.trim()))
+            .collect::<Result<Vec<Rgb>, String>>()?;
+        let (background, foreground) = match colors.as_slice() {
+            [background, foreground, ..] if colors.len() <= 4 => (*background, *foreground),
+            _ => return Err(format!("expected 2 to 4 colors, got {}", colors.len())),
+        };
+        let defaults = [
+            background,
+            foreground,
+            mix(background, foreground, 2, 3),
+            mix(background, foreground, 1, 3),
+        ];
+        let mut palette = Palette {
+            name: "custom".to_string(),
+            colors: defaults,
+        };
+        palette.colors[..colors.len()].copy_from_slice(&colors);
+        Ok(palette)
+    }
+}
+
+// `#rrggbb`, the `#` is optional
+fn parse_color(text: &str) -> Result<Rgb, String> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    let invalid = || format!("invalid color `{}` (expected #rrggbb)", text);
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut color = [0; 3];
+    for (index, channel) in color.iter_mut().enumerate() {
+        *channel = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(color)
+}
+
+// `from` moved `numerator / denominator` of the way to `to`
+fn mix(from: Rgb, to: Rgb, numerator: u32, denominator: u32) -> Rgb {
+    let channel = |from: u8, to: u8| {
+        let (from, to) = (from as u32, to as u32);
+        ((from * (denominator - numerator) + to * numerator) / denominator) as u8
+    };
+    [
+        channel(from[0], to[0]),
+        channel(from[1], to[1]),
+        channel(from[2], to[2]),
+    ]
+}
+
+#[cfg(test)]
+#[path = "./palette_tests.rs"]
+mod palette_tests;
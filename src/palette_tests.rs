@@ -0,0 +1,80 @@
+use super::*;
+
+#[test]
+fn test_named() {
+    assert_eq!(Palette::default().name, "classic");
+    assert_eq!(Palette::default().colors[1], [0xFF, 0xFF, 0xFF]);
+    assert_eq!(
+        "octo".parse::<Palette>().unwrap().colors[0],
+        [0x99, 0x66, 0x00]
+    );
+    assert_eq!(
+        "sepia".parse::<Palette>(),
+        Err("unknown palette `sepia` (expected classic, amber, green, lcd, octo, high-contrast, colorblind or hex colors)".to_string())
+    );
+}
+
+#[test]
+fn test_named_colors_are_distinct() {
+    // XO-CHIP games draw with all four, so none may blend into another
+    for (name, colors) in NAMED {
+        for (index, a) in colors.iter().enumerate() {
+            for b in &colors[index + 1..] {
+                let distance: u32 = a.iter().zip(b).map(|(a, b)| a.abs_diff(*b) as u32).sum();
+                assert!(distance >= 96, "{}: {:?} and {:?}", name, a, b);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_custom() {
+    // the in-between planes are filled in, which for black and white are the classic greys
+    let palette: Palette = "#000000,#ffffff".parse().unwrap();
+    assert_eq!(palette.colors, Palette::default().colors);
+    assert_eq!(palette.name, "custom");
+
+    let palette: Palette = "102030, #405060 ,#708090,#a0b0c0".parse().unwrap();
+    assert_eq!(
+        palette.colors,
+        [
+            [0x10, 0x20, 0x30],
+            [0x40, 0x50, 0x60],
+            [0x70, 0x80, 0x90],
+            [0xA0, 0xB0, 0xC0]
+        ]
+    );
+    assert_eq!(palette.to_string(), "#102030,#405060,#708090,#a0b0c0");
+}
+
+#[test]
+fn test_custom_errors() {
+    assert_eq!(
+        "#000000".parse::<Palette>(),
+        Err("expected 2 to 4 colors, got 1".to_string())
+    );
+    assert_eq!(
+        "#000000,#111111,#222222,#333333,#444444".parse::<Palette>(),
+        Err("expected 2 to 4 colors, got 5".to_string())
+    );
+    assert_eq!(
+        "#000000,#fff".parse::<Palette>(),
+        Err("invalid color `#fff` (expected #rrggbb)".to_string())
+    );
+    assert!("#000000,#gggggg".parse::<Palette>().is_err());
+}
+
+#[test]
+fn test_next() {
+    let mut palette = Palette::default();
+    let mut names = Vec::new();
+    for _ in 0..NAMED.len() {
+        palette = palette.next();
+        names.push(palette.name.clone());
+    }
+    assert_eq!(names.last().unwrap(), "classic");
+    assert_eq!(names[0], "amber");
+
+    let custom: Palette = "#000000,#ffffff".parse().unwrap();
+    assert_eq!(custom.next().name, "classic");
+}
@@ -0,0 +1,241 @@
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+use crate::display::{
+    Display, HEIGHT, HIRES_HEIGHT, HIRES_WIDTH, LORES_HEIGHT, LORES_WIDTH, PLANE_MASK, WIDTH,
+};
+
+const DEFAULT_DECAY: f32 = 0.5;
+const DEFAULT_FRAMES: usize = 2;
+
+type Cells = [[u8; WIDTH]; HEIGHT];
+
+// how the framebuffer is smoothed before it's shown, to hide the flicker of
+// sprites being erased and redrawn with XOR
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Persistence {
+    Off,
+    // pixels that go dark lose `decay` of their brightness every frame, like phosphor
+    Fade(f32),
+    // a pixel is lit if it was in any of the last N frames
+    Max(usize),
+    // the screen only changes at vblank, and a frame that only erased is held
+    // back once so the redraw in the next frame lands before it's shown
+    Vblank,
+}
+
+impl fmt::Display for Persistence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Persistence::Off => write!(f, "off"),
+            Persistence::Fade(decay) => write!(f, "fade:{}", decay),
+            Persistence::Max(frames) => write!(f, "max:{}", frames),
+            Persistence::Vblank => write!(f, "vblank"),
+        }
+    }
+}
+
+// off, fade[:decay], max[:frames] or vblank
+impl FromStr for Persistence {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Persistence, String> {
+        let (mode, value) = match s.split_once(':') {
+            Some((mode, value)) => (mode, Some(value)),
+            None => (s, None),
+        };
+        match (mode, value) {
+            ("off", None) => Ok(Persistence::Off),
+            ("vblank", None) => Ok(Persistence::Vblank),
+            ("fade", None) => Ok(Persistence::Fade(DEFAULT_DECAY)),
+            ("fade", Some(value)) => match value.parse::<f32>() {
+                Ok(decay) if decay > 0.0 && decay <= 1.0 => Ok(Persistence::Fade(decay)),
+                _ => Err(format!(
+                    "invalid fade decay `{}` (expected a number above 0.0, up to 1.0)",
+                    value
+                )),
+            },
+            ("max", None) => Ok(Persistence::Max(DEFAULT_FRAMES)),
+            ("max", Some(value)) => match value.parse::<usize>() {
+                Ok(frames) if frames > 0 => Ok(Persistence::Max(frames)),
+                _ => Err(format!(
+                    "invalid frame count `{}` (expected a whole number above 0)",
+                    value
+                )),
+            },
+            _ => Err(format!(
+                "unknown persistence `{}` (expected off, fade[:decay], max[:frames] or vblank)",
+                s
+            )),
+        }
+    }
+}
+
+// how lit each bitplane of each pixel is, from 0.0 to 1.0. laid out like the
+// framebuffer, hi-res sized with lo-res only using the top-left corner
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shades {
+    pub levels: Box<[[[f32; 2]; WIDTH]; HEIGHT]>,
+    pub hires: bool,
+}
+
+impl Shades {
+    fn blank(hires: bool) -> Shades {
+        Shades {
+            levels: Box::new([[[0.0; 2]; WIDTH]; HEIGHT]),
+            hires,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        if self.hires {
+            HIRES_WIDTH
+        } else {
+            LORES_WIDTH
+        }
+    }
+
+    pub fn height(&self) -> usize {
+        if self.hires {
+            HIRES_HEIGHT
+        } else {
+            LORES_HEIGHT
+        }
+    }
+
+    // how much of each palette color goes into a pixel, indexed like a framebuffer cell
+    pub fn weights(&self, x: usize, y: usize) -> [f32; 4] {
+        let [plane1, plane2] = self.levels[y][x];
+        [
+            (1.0 - plane1) * (1.0 - plane2),
+            plane1 * (1.0 - plane2),
+            (1.0 - plane1) * plane2,
+            plane1 * plane2,
+        ]
+    }
+
+    // the framebuffer cell a pixel is closest to, planes at least half lit count as set
+    pub fn cell(&self, x: usize, y: usize) -> u8 {
+        let [plane1, plane2] = self.levels[y][x];
+        (plane1 >= 0.5) as u8 | ((plane2 >= 0.5) as u8) << 1
+    }
+
+    fn set_cells(&mut self, cells: &Cells) {
+        for (levels, row) in self.levels.iter_mut().zip(cells) {
+            for (level, &cell) in levels.iter_mut().zip(row) {
+                *level = [(cell & 1) as f32, ((cell >> 1) & 1) as f32];
+            }
+        }
+    }
+}
+
+impl From<&Display> for Shades {
+    fn from(display: &Display) -> Shades {
+        let mut shades = Shades::blank(display.hires);
+        shades.set_cells(&display.memory);
+        shades
+    }
+}
+
+// sits between the framebuffer and whatever shows it, fed once per frame at vblank
+pub struct Blender {
+    mode: Persistence,
+    shades: Shades,
+    // the most recent frames, newest first; only as many as the mode needs
+    history: VecDeque<Box<Cells>>,
+    // a frame that only erased is being held back
+    holding: bool,
+}
+
+impl Blender {
+    pub fn new(mode: Persistence) -> Blender {
+        Blender {
+            mode,
+            shades: Shades::blank(false),
+            history: VecDeque::new(),
+            holding: false,
+        }
+    }
+
+    pub fn mode(&self) -> Persistence {
+        self.mode
+    }
+
+    pub fn shades(&self) -> &Shades {
+        &self.shades
+    }
+
+    pub fn update(&mut self, display: &Display) {
+        // switching resolution clears the screen, nothing should linger across it
+        if display.hires != self.shades.hires {
+            self.shades = Shades::blank(display.hires);
+            self.history.clear();
+            self.holding = false;
+        }
+
+        match self.mode {
+            Persistence::Off => self.shades.set_cells(&display.memory),
+            Persistence::Fade(decay) => {
+                for (levels, row) in self.shades.levels.iter_mut().zip(&display.memory) {
+                    for (level, &cell) in levels.iter_mut().zip(row) {
+                        for (plane, value) in level.iter_mut().enumerate() {
+                            *value = if cell & (1 << plane) != 0 {
+                                1.0
+                            } else {
+                                // snapped to dark once it can't change an 8-bit color
+                                let faded = *value * (1.0 - decay);
+                                if faded < 1.0 / 255.0 {
+                                    0.0
+                                } else {
+                                    faded
+                                }
+                            };
+                        }
+                    }
+                }
+            }
+            Persistence::Max(frames) => {
+                self.history.push_front(Box::new(display.memory));
+                self.history.truncate(frames);
+                let mut cells: Cells = [[0; WIDTH]; HEIGHT];
+                for frame in &self.history {
+                    for (row, frame_row) in cells.iter_mut().zip(frame.iter()) {
+                        for (cell, &frame_cell) in row.iter_mut().zip(frame_row) {
+                            *cell |= frame_cell & PLANE_MASK;
+                        }
+                    }
+                }
+                self.shades.set_cells(&cells);
+            }
+            Persistence::Vblank => {
+                let previous = self.history.front();
+                let erased = previous.is_some_and(|previous| {
+                    let changes = previous
+                        .iter()
+                        .flatten()
+                        .zip(display.memory.iter().flatten());
+                    let mut erased = false;
+                    for (&old, &new) in changes {
+                        if new & !old != 0 {
+                            return false;
+                        }
+                        erased |= old & !new != 0;
+                    }
+                    erased
+                });
+                self.history.clear();
+                self.history.push_front(Box::new(display.memory));
+
+                if erased && !self.holding {
+                    self.holding = true;
+                } else {
+                    self.holding = false;
+                    self.shades.set_cells(&display.memory);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./persistence_tests.rs"]
+mod persistence_tests;
@@ -0,0 +1,114 @@
+use super::*;
+
+fn display_with(pixels: &[(usize, usize, u8)]) -> Display {
+    let mut display = Display::new();
+    for &(x, y, plane) in pixels {
+        display.set_pixel(x, y, plane, true);
+    }
+    display
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!("off".parse(), Ok(Persistence::Off));
+    assert_eq!("fade".parse(), Ok(Persistence::Fade(0.5)));
+    assert_eq!("fade:0.25".parse(), Ok(Persistence::Fade(0.25)));
+    assert_eq!("max".parse(), Ok(Persistence::Max(2)));
+    assert_eq!("max:4".parse(), Ok(Persistence::Max(4)));
+    assert_eq!("vblank".parse(), Ok(Persistence::Vblank));
+    assert_eq!(Persistence::Fade(0.25).to_string(), "fade:0.25");
+    assert_eq!(
+        "fade:0".parse::<Persistence>(),
+        Err("invalid fade decay `0` (expected a number above 0.0, up to 1.0)".to_string())
+    );
+    assert!("max:0".parse::<Persistence>().is_err());
+    assert!("vblank:2".parse::<Persistence>().is_err());
+    assert!("blur".parse::<Persistence>().is_err());
+}
+
+#[test]
+fn test_shades_from_display() {
+    let shades = Shades::from(&display_with(&[(0, 0, 1), (1, 0, 2), (2, 0, 3)]));
+    assert_eq!(
+        shades.levels[0][..4],
+        [[1.0, 0.0], [0.0, 1.0], [1.0, 1.0], [0.0, 0.0]]
+    );
+    assert_eq!(shades.weights(2, 0), [0.0, 0.0, 0.0, 1.0]);
+    assert_eq!(shades.cell(1, 0), 2);
+    assert_eq!((shades.width(), shades.height()), (64, 32));
+}
+
+#[test]
+fn test_off() {
+    let mut blender = Blender::new(Persistence::Off);
+    blender.update(&display_with(&[(0, 0, 1)]));
+    blender.update(&Display::new());
+    assert_eq!(blender.shades().cell(0, 0), 0);
+}
+
+#[test]
+fn test_fade() {
+    let mut blender = Blender::new(Persistence::Fade(0.5));
+    blender.update(&display_with(&[(0, 0, 1)]));
+    blender.update(&Display::new());
+    assert_eq!(blender.shades().levels[0][0], [0.5, 0.0]);
+    assert_eq!(blender.shades().weights(0, 0), [0.5, 0.5, 0.0, 0.0]);
+    blender.update(&Display::new());
+    assert_eq!(blender.shades().levels[0][0], [0.25, 0.0]);
+
+    // fades all the way out instead of lingering at tiny values
+    for _ in 0..10 {
+        blender.update(&Display::new());
+    }
+    assert_eq!(blender.shades().levels[0][0], [0.0, 0.0]);
+
+    // redrawn pixels are back to full brightness straight away
+    blender.update(&display_with(&[(0, 0, 1)]));
+    assert_eq!(blender.shades().levels[0][0], [1.0, 0.0]);
+}
+
+#[test]
+fn test_max() {
+    let mut blender = Blender::new(Persistence::Max(2));
+    blender.update(&display_with(&[(0, 0, 1)]));
+    blender.update(&display_with(&[(1, 0, 2)]));
+    assert_eq!(blender.shades().cell(0, 0), 1);
+    assert_eq!(blender.shades().cell(1, 0), 2);
+    blender.update(&Display::new());
+    assert_eq!(blender.shades().cell(0, 0), 0);
+    assert_eq!(blender.shades().cell(1, 0), 2);
+    blender.update(&Display::new());
+    assert_eq!(blender.shades().cell(1, 0), 0);
+}
+
+#[test]
+fn test_vblank() {
+    let mut blender = Blender::new(Persistence::Vblank);
+    blender.update(&display_with(&[(0, 0, 1)]));
+    assert_eq!(blender.shades().cell(0, 0), 1);
+
+    // the sprite is erased at the end of one frame and redrawn one pixel over
+    // in the next, the blank frame in between never shows
+    blender.update(&Display::new());
+    assert_eq!(blender.shades().cell(0, 0), 1);
+    blender.update(&display_with(&[(1, 0, 1)]));
+    assert_eq!(blender.shades().cell(0, 0), 0);
+    assert_eq!(blender.shades().cell(1, 0), 1);
+
+    // an erase that isn't followed by a redraw only waits one frame
+    blender.update(&Display::new());
+    assert_eq!(blender.shades().cell(1, 0), 1);
+    blender.update(&Display::new());
+    assert_eq!(blender.shades().cell(1, 0), 0);
+}
+
+#[test]
+fn test_resolution_change() {
+    let mut blender = Blender::new(Persistence::Max(4));
+    blender.update(&display_with(&[(0, 0, 1)]));
+    let mut display = Display::new();
+    display.set_hires(true);
+    blender.update(&display);
+    assert!(blender.shades().hires);
+    assert_eq!(blender.shades().cell(0, 0), 0);
+}
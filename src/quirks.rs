@@ -0,0 +1,149 @@
+use std::{fmt, str::FromStr};
+
+use crate::cpu::Platform;
+
+// behaviours that differ between CHIP-8 interpreters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quirks {
+    // 8XY6/8XYE shift VY into VX, instead of shifting VX in place
+    pub shift_uses_vy: bool,
+    // FX55/FX65 leave I pointing past the last register they touched
+    pub increment_i: bool,
+    // with increment_i, I only moves on by X, one short: CHIP-48's off-by-one
+    pub increment_i_by_x: bool,
+    // 8XY1/8XY2/8XY3 reset VF to 0
+    pub vf_reset: bool,
+    // BNNN jumps to XNN + VX, instead of NNN + V0
+    pub jump_uses_vx: bool,
+    // DXYN clips sprites at the screen edge, instead of wrapping them around
+    pub clip_sprites: bool,
+    // DXYN waits for the next vertical blank, so a ROM draws at most one sprite a frame
+    pub display_wait: bool,
+}
+
+impl Quirks {
+    // names match the command line flags
+    pub fn flags(&self) -> [(&'static str, bool); 7] {
+        [
+            ("shift-uses-vy", self.shift_uses_vy),
+            ("increment-i", self.increment_i),
+            ("increment-i-by-x", self.increment_i_by_x),
+            ("vf-reset", self.vf_reset),
+            ("jump-uses-vx", self.jump_uses_vx),
+            ("clip-sprites", self.clip_sprites),
+            ("display-wait", self.display_wait),
+        ]
+    }
+
+    pub fn set(&mut self, name: &str, value: bool) -> Result<(), String> {
+        let flag = match name {
+            "shift-uses-vy" => &mut self.shift_uses_vy,
+            "increment-i" => &mut self.increment_i,
+            "increment-i-by-x" => &mut self.increment_i_by_x,
+            "vf-reset" => &mut self.vf_reset,
+            "jump-uses-vx" => &mut self.jump_uses_vx,
+            "clip-sprites" => &mut self.clip_sprites,
+            "display-wait" => &mut self.display_wait,
+            _ => return Err(format!("unknown quirk `{}`", name)),
+        };
+        *flag = value;
+        Ok(())
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        Profile::CosmacVip.quirks()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    CosmacVip,
+    Chip48,
+    SuperChipModern,
+    SuperChipLegacy,
+    XoChip,
+}
+
+impl Profile {
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Profile::CosmacVip => Quirks {
+                shift_uses_vy: true,
+                increment_i: true,
+                increment_i_by_x: false,
+                vf_reset: true,
+                jump_uses_vx: false,
+                clip_sprites: true,
+                display_wait: true,
+            },
+            Profile::Chip48 | Profile::SuperChipModern | Profile::SuperChipLegacy => Quirks {
+                shift_uses_vy: false,
+                // CHIP-48 moved I on by one less than the VIP, SUPER-CHIP stopped moving it
+                increment_i: self == Profile::Chip48,
+                increment_i_by_x: self == Profile::Chip48,
+                vf_reset: false,
+                jump_uses_vx: true,
+                clip_sprites: true,
+                // SUPER-CHIP 1.1 still waited in lo-res, later interpreters never did
+                display_wait: self == Profile::SuperChipLegacy,
+            },
+            Profile::XoChip => Quirks {
+                shift_uses_vy: true,
+                increment_i: true,
+                increment_i_by_x: false,
+                vf_reset: false,
+                jump_uses_vx: false,
+                clip_sprites: false,
+                display_wait: false,
+            },
+        }
+    }
+
+    pub fn platform(self) -> Platform {
+        match self {
+            Profile::CosmacVip | Profile::Chip48 => Platform::Chip8,
+            Profile::SuperChipModern | Profile::SuperChipLegacy => Platform::SuperChip,
+            Profile::XoChip => Platform::XoChip,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Profile::CosmacVip => "vip",
+            Profile::Chip48 => "chip48",
+            Profile::SuperChipModern => "schip",
+            Profile::SuperChipLegacy => "schip-legacy",
+            Profile::XoChip => "xochip",
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Profile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Profile, String> {
+        match s {
+            "vip" => Ok(Profile::CosmacVip),
+            "chip48" => Ok(Profile::Chip48),
+            "schip" => Ok(Profile::SuperChipModern),
+            "schip-legacy" => Ok(Profile::SuperChipLegacy),
+            "xochip" => Ok(Profile::XoChip),
+            _ => Err(format!(
+                "unknown profile `{}` (expected vip, chip48, schip, schip-legacy or xochip)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./quirks_tests.rs"]
+mod quirks_tests;
@@ -0,0 +1,44 @@
+use super::*;
+
+#[test]
+fn test_profiles_differ() {
+    let vip = Profile::CosmacVip.quirks();
+    let chip48 = Profile::Chip48.quirks();
+    let modern = Profile::SuperChipModern.quirks();
+    let legacy = Profile::SuperChipLegacy.quirks();
+    let xochip = Profile::XoChip.quirks();
+
+    // CHIP-48 shifts VX in place, jumps with VX and moves I on by X
+    assert!(!chip48.shift_uses_vy && chip48.jump_uses_vx && !chip48.vf_reset);
+    assert!(chip48.increment_i && chip48.increment_i_by_x);
+    // SUPER-CHIP leaves I alone, 1.1 still waits for vblank
+    assert!(!modern.increment_i && !legacy.increment_i);
+    assert!(legacy.display_wait && !modern.display_wait);
+    assert_eq!(
+        Quirks {
+            display_wait: false,
+            ..legacy
+        },
+        modern
+    );
+    // only the VIP resets VF, only XO-CHIP wraps sprites
+    assert!(vip.vf_reset && vip.display_wait && !vip.increment_i_by_x);
+    assert!(!xochip.clip_sprites && xochip.increment_i && !xochip.vf_reset);
+
+    let all = [vip, chip48, modern, legacy, xochip];
+    for (index, quirks) in all.iter().enumerate() {
+        assert!(!all[index + 1..].contains(quirks));
+    }
+}
+
+#[test]
+fn test_set() {
+    let mut quirks = Quirks::default();
+    quirks.set("increment-i-by-x", true).unwrap();
+    assert!(quirks.increment_i_by_x);
+    assert!(quirks.set("wrap", true).is_err());
+    assert!(quirks
+        .flags()
+        .iter()
+        .all(|&(name, value)| Quirks::default().set(name, value).is_ok()));
+}
@@ -0,0 +1,91 @@
+use std::{fmt, str::FromStr};
+
+use crate::{
+    display::{Display, HEIGHT, WIDTH},
+    palette::Rgb,
+    persistence::Shades,
+};
+
+// bytes in an RGBA frame, which is always hi-res sized
+pub const FRAME_SIZE: usize = WIDTH * HEIGHT * 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scaling {
+    // the largest whole multiple of the framebuffer that fits, so every pixel is the same size
+    Integer,
+    // as large as fits while keeping the aspect ratio
+    Fit,
+}
+
+impl fmt::Display for Scaling {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Scaling::Integer => "integer",
+            Scaling::Fit => "fit",
+        })
+    }
+}
+
+impl FromStr for Scaling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Scaling, String> {
+        match s {
+            "integer" => Ok(Scaling::Integer),
+            "fit" => Ok(Scaling::Fit),
+            _ => Err(format!("unknown scaling `{}` (expected integer or fit)", s)),
+        }
+    }
+}
+
+// where the frame goes in a window of the given size, centred with black bars
+// around it: x, y, width, height
+pub fn letterbox(window: (u32, u32), scaling: Scaling) -> (i32, i32, u32, u32) {
+    let (frame_width, frame_height) = (WIDTH as u32, HEIGHT as u32);
+    let scale = (window.0 / frame_width).min(window.1 / frame_height);
+    let (width, height) = match scaling {
+        // a window smaller than the framebuffer gets a shrunk frame instead of nothing
+        Scaling::Integer if scale > 0 => (frame_width * scale, frame_height * scale),
+        _ => {
+            let scale =
+                (window.0 as f32 / frame_width as f32).min(window.1 as f32 / frame_height as f32);
+            (
+                (frame_width as f32 * scale).round() as u32,
+                (frame_height as f32 * scale).round() as u32,
+            )
+        }
+    };
+    (
+        ((window.0 - width) / 2) as i32,
+        ((window.1 - height) / 2) as i32,
+        width,
+        height,
+    )
+}
+
+// fills an RGBA frame from the framebuffer, lo-res pixels are drawn twice as large
+pub fn rgba(display: &Display, colors: &[Rgb; 4], frame: &mut [u8]) {
+    rgba_shaded(&Shades::from(display), colors, frame);
+}
+
+// the same from smoothed levels, partly lit pixels blend the palette colors
+pub fn rgba_shaded(shades: &Shades, colors: &[Rgb; 4], frame: &mut [u8]) {
+    let scale = WIDTH / shades.width();
+    for (y, row) in frame.chunks_exact_mut(WIDTH * 4).enumerate() {
+        for (x, pixel) in row.chunks_exact_mut(4).enumerate() {
+            let weights = shades.weights(x / scale, y / scale);
+            let mut rgb = [0.0f32; 3];
+            for (color, weight) in colors.iter().zip(weights) {
+                for (channel, &value) in rgb.iter_mut().zip(color) {
+                    *channel += value as f32 * weight;
+                }
+            }
+            let [r, g, b] = rgb.map(|channel| channel.round() as u8);
+            pixel.copy_from_slice(&[r, g, b, 0xFF]);
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./render_tests.rs"]
+mod render_tests;
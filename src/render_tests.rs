@@ -0,0 +1,53 @@
+use super::*;
+use crate::palette::Palette;
+
+#[test]
+fn test_letterbox_integer() {
+    // exactly the default window
+    assert_eq!(letterbox((1024, 512), Scaling::Integer), (0, 0, 1024, 512));
+    // whole multiples only, bars on both axes
+    assert_eq!(letterbox((1000, 600), Scaling::Integer), (52, 76, 896, 448));
+    // smaller than the framebuffer falls back to fitting
+    assert_eq!(letterbox((64, 64), Scaling::Integer), (0, 16, 64, 32));
+}
+
+#[test]
+fn test_letterbox_fit() {
+    assert_eq!(letterbox((1920, 1080), Scaling::Fit), (0, 60, 1920, 960));
+    assert_eq!(letterbox((500, 1000), Scaling::Fit), (0, 375, 500, 250));
+}
+
+#[test]
+fn test_rgba_lores() {
+    let mut display = Display::new();
+    display.set_pixel(1, 0, 1, true);
+    display.set_pixel(2, 0, 2, true);
+    let mut frame = vec![0; FRAME_SIZE];
+    rgba(&display, &Palette::default().colors, &mut frame);
+
+    let pixel = |x: usize, y: usize| &frame[(y * WIDTH + x) * 4..][..4];
+    assert_eq!(pixel(0, 0), [0, 0, 0, 255]);
+    // each lo-res pixel covers 2x2 frame pixels
+    assert_eq!(pixel(2, 0), [255, 255, 255, 255]);
+    assert_eq!(pixel(3, 1), [255, 255, 255, 255]);
+    assert_eq!(pixel(4, 0), [170, 170, 170, 255]);
+    assert_eq!(pixel(6, 0), [0, 0, 0, 255]);
+}
+
+#[test]
+fn test_rgba_hires() {
+    let mut display = Display::new();
+    display.hires = true;
+    display.set_pixel(127, 63, 3, true);
+    let mut frame = vec![0; FRAME_SIZE];
+    rgba(&display, &Palette::default().colors, &mut frame);
+
+    assert_eq!(&frame[FRAME_SIZE - 8..], [0, 0, 0, 255, 85, 85, 85, 255]);
+}
+
+#[test]
+fn test_scaling_from_str() {
+    assert_eq!("integer".parse(), Ok(Scaling::Integer));
+    assert_eq!("fit".parse(), Ok(Scaling::Fit));
+    assert!("stretch".parse::<Scaling>().is_err());
+}
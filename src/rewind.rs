@@ -0,0 +1,185 @@
+use std::collections::VecDeque;
+
+use crate::Machine;
+
+// a step back from the next newer snapshot
+enum Delta {
+    // runs of (unchanged length, changed length, changed bytes)
+    Runs(Vec<u8>),
+    // the state changed size, e.g. a different platform was loaded
+    Full(Vec<u8>),
+}
+
+// history of save states taken every `interval` frames; only the newest is
+// kept whole, older ones are stored as deltas walking backwards from it
+pub struct Rewind {
+    interval: u32,
+    capacity: usize,
+    frames_since_snapshot: u32,
+    // frames rewound since the machine last moved back to a snapshot
+    frames_rewound: u32,
+    newest: Option<Vec<u8>>,
+    deltas: VecDeque<Delta>,
+}
+
+impl Rewind {
+    pub fn new(interval: u32, capacity: usize) -> Rewind {
+        Rewind {
+            interval: interval.max(1),
+            capacity: capacity.max(1),
+            frames_since_snapshot: 0,
+            frames_rewound: 0,
+            newest: None,
+            deltas: VecDeque::new(),
+        }
+    }
+
+    // call once per emulated frame, before running it
+    pub fn record(&mut self, machine: &Machine) {
+        self.frames_since_snapshot += 1;
+        if self.newest.is_some() && self.frames_since_snapshot < self.interval {
+            return;
+        }
+        self.frames_since_snapshot = 0;
+
+        let state = machine.save_state();
+        if let Some(previous) = self.newest.take() {
+            self.deltas.push_back(diff(&state, &previous));
+            if self.deltas.len() >= self.capacity {
+                self.deltas.pop_front();
+            }
+        }
+        self.newest = Some(state);
+    }
+
+    // call once per frame instead of `record` to step back one frame. the
+    // newest snapshot is restored and dropped from the history once the frames
+    // since it are used up, so game time runs backwards at the speed it was
+    // played; returns false once only the oldest one is left. the keypad keeps
+    // its live state
+    pub fn rewind(&mut self, machine: &mut Machine) -> bool {
+        if self.newest.is_none() {
+            return false;
+        }
+        // the machine is one frame further on than `record` has counted
+        if self.frames_rewound < self.frames_since_snapshot {
+            self.frames_rewound += 1;
+            return true;
+        }
+        self.frames_rewound = 0;
+
+        let Some(state) = self.newest.take() else {
+            return false;
+        };
+        let keys = machine.cpu.keypad.keys;
+        // the history belongs to a ROM that's no longer loaded
+        if machine.load_state(&state).is_err() {
+            self.clear();
+            return false;
+        }
+        machine.cpu.keypad.keys = keys;
+
+        match self.deltas.pop_back() {
+            Some(delta) => {
+                // the restored state is `interval` frames after the one before it
+                self.frames_since_snapshot = self.interval - 1;
+                self.newest = Some(apply(&state, delta));
+                true
+            }
+            // the oldest snapshot is what's running now, `record` takes it again
+            None => {
+                self.frames_since_snapshot = 0;
+                false
+            }
+        }
+    }
+
+    // number of snapshots that can be rewound to
+    pub fn len(&self) -> usize {
+        self.deltas.len() + self.newest.is_some() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.newest.is_none()
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.frames_since_snapshot = 0;
+        self.frames_rewound = 0;
+    }
+}
+
+fn diff(from: &[u8], to: &[u8]) -> Delta {
+    if from.len() != to.len() {
+        return Delta::Full(to.to_vec());
+    }
+
+    let mut runs = Vec::new();
+    let mut pos = 0;
+    while pos < to.len() {
+        let unchanged = from[pos..]
+            .iter()
+            .zip(&to[pos..])
+            .take_while(|(a, b)| a == b)
+            .count();
+        let start = pos + unchanged;
+        let changed = from[start..]
+            .iter()
+            .zip(&to[start..])
+            .take_while(|(a, b)| a != b)
+            .count();
+        write_varint(&mut runs, unchanged);
+        write_varint(&mut runs, changed);
+        runs.extend(&to[start..start + changed]);
+        pos = start + changed;
+    }
+    Delta::Runs(runs)
+}
+
+fn apply(from: &[u8], delta: Delta) -> Vec<u8> {
+    let runs = match delta {
+        Delta::Full(state) => return state,
+        Delta::Runs(runs) => runs,
+    };
+
+    let mut state = from.to_vec();
+    let (mut pos, mut offset) = (0, 0);
+    while offset < runs.len() {
+        let unchanged = read_varint(&runs, &mut offset);
+        let changed = read_varint(&runs, &mut offset);
+        pos += unchanged;
+        state[pos..pos + changed].copy_from_slice(&runs[offset..offset + changed]);
+        pos += changed;
+        offset += changed;
+    }
+    state
+}
+
+// LEB128, most runs fit in a single byte
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], offset: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    loop {
+        let byte = data[*offset];
+        *offset += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return value;
+        }
+        shift += 7;
+    }
+}
+
+#[cfg(test)]
+#[path = "./rewind_tests.rs"]
+mod rewind_tests;
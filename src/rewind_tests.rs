@@ -0,0 +1,139 @@
+use super::*;
+use crate::{cpu::Platform, quirks::Quirks, TIMER_FREQUENCY};
+
+// V0 counts frames: add one, jump back, two instructions a frame
+fn build_machine() -> Machine {
+    let mut machine = Machine::new(Platform::Chip8, Quirks::default());
+    machine.load_rom(&[0x70, 0x01, 0x12, 0x00]);
+    machine.ips = 2 * TIMER_FREQUENCY;
+    machine
+}
+
+fn run(machine: &mut Machine, rewind: &mut Rewind, frames: usize) {
+    for _ in 0..frames {
+        rewind.record(machine);
+        machine.run_frame().unwrap();
+    }
+}
+
+#[test]
+fn test_rewind_frame_by_frame() {
+    let mut machine = build_machine();
+    let mut rewind = Rewind::new(1, 100);
+    run(&mut machine, &mut rewind, 10);
+    assert_eq!(machine.cpu.v[0], 10);
+
+    for expected in (7..10).rev() {
+        assert!(rewind.rewind(&mut machine));
+        assert_eq!(machine.cpu.v[0], expected);
+    }
+
+    // playing on after a rewind continues from the restored state
+    run(&mut machine, &mut rewind, 2);
+    assert_eq!(machine.cpu.v[0], 9);
+    assert!(rewind.rewind(&mut machine));
+    assert_eq!(machine.cpu.v[0], 8);
+}
+
+#[test]
+fn test_rewind_keeps_game_speed() {
+    // snapshots at frames 0, 3, 6 and 9, the machine is at frame 11
+    let mut machine = build_machine();
+    let mut rewind = Rewind::new(3, 100);
+    run(&mut machine, &mut rewind, 11);
+
+    // rewinding n frames lands on the newest snapshot at most 11 - n
+    let mut rewound = 0;
+    for (frames, expected) in [(1, 11), (2, 9), (4, 9), (5, 6), (8, 3), (10, 3)] {
+        while rewound < frames {
+            assert!(rewind.rewind(&mut machine));
+            rewound += 1;
+        }
+        assert_eq!(machine.cpu.v[0], expected, "after {} frames", frames);
+    }
+    assert!(!rewind.rewind(&mut machine));
+    assert_eq!(machine.cpu.v[0], 0);
+
+    // playing on snapshots again every 3 frames from the restored state
+    run(&mut machine, &mut rewind, 4);
+    assert_eq!(rewind.len(), 2);
+    assert!(rewind.rewind(&mut machine));
+    assert_eq!(machine.cpu.v[0], 3);
+}
+
+#[test]
+fn test_stops_at_oldest() {
+    let mut machine = build_machine();
+    let mut rewind = Rewind::new(1, 100);
+    run(&mut machine, &mut rewind, 2);
+
+    assert!(rewind.rewind(&mut machine));
+    assert!(!rewind.rewind(&mut machine));
+    assert_eq!(machine.cpu.v[0], 0);
+    assert!(!rewind.rewind(&mut machine));
+    assert_eq!(machine.cpu.v[0], 0);
+}
+
+#[test]
+fn test_interval_and_capacity() {
+    let mut machine = build_machine();
+    let mut rewind = Rewind::new(3, 100);
+    run(&mut machine, &mut rewind, 9);
+    assert_eq!(rewind.len(), 3);
+
+    for _ in 0..3 {
+        assert!(rewind.rewind(&mut machine));
+    }
+    assert_eq!(machine.cpu.v[0], 6);
+
+    let mut rewind = Rewind::new(1, 4);
+    run(&mut machine, &mut rewind, 10);
+    assert_eq!(rewind.len(), 4);
+    while rewind.rewind(&mut machine) {}
+    assert_eq!(machine.cpu.v[0], 12);
+}
+
+#[test]
+fn test_keeps_live_keys() {
+    let mut machine = build_machine();
+    let mut rewind = Rewind::new(1, 100);
+    run(&mut machine, &mut rewind, 3);
+    machine.set_key(0x5, true);
+
+    rewind.rewind(&mut machine);
+    assert!(machine.cpu.keypad.keys[0x5]);
+}
+
+#[test]
+fn test_diff() {
+    let from = [1, 2, 3, 4, 5, 6, 7, 8];
+    let to = [1, 9, 9, 4, 5, 6, 7, 0];
+    let Delta::Runs(runs) = diff(&from, &to) else {
+        panic!("expected runs");
+    };
+    assert_eq!(runs, [1, 2, 9, 9, 4, 1, 0]);
+    assert_eq!(apply(&from, Delta::Runs(runs)), to);
+
+    assert_eq!(apply(&from, diff(&from, &[1, 2])), [1, 2]);
+    assert_eq!(apply(&from, diff(&from, &from)), from);
+}
+
+#[test]
+fn test_varint() {
+    let mut out = Vec::new();
+    write_varint(&mut out, 0x10000);
+    assert_eq!(out, [0x80, 0x80, 0x04]);
+    assert_eq!(read_varint(&out, &mut 0), 0x10000);
+}
+
+#[test]
+fn test_history_is_dropped_for_another_rom() {
+    let mut machine = build_machine();
+    let mut rewind = Rewind::new(1, 100);
+    run(&mut machine, &mut rewind, 3);
+    machine.load_rom(&[0x12, 0x00]);
+
+    assert!(!rewind.rewind(&mut machine));
+    assert!(rewind.is_empty());
+    assert_eq!(machine.cpu.memory[0x200..0x202], [0x12, 0x00]);
+}
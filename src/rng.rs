@@ -0,0 +1,126 @@
+use std::{fmt, str::FromStr};
+
+// where CXKK gets its random bytes from
+pub trait RandomSource {
+    fn next_byte(&mut self) -> u8;
+    // generator kind and state, enough for `RngMode::create` to rebuild it
+    fn save(&self) -> (RngMode, u64);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RngMode {
+    Seeded,
+    Vip,
+}
+
+impl RngMode {
+    pub fn create(self, seed: u64) -> Box<dyn RandomSource> {
+        match self {
+            RngMode::Seeded => Box::new(SplitMix64 { state: seed }),
+            RngMode::Vip => Box::new(VipRandom { r9: seed as u16 }),
+        }
+    }
+
+    // the id used in save states
+    pub fn id(self) -> u8 {
+        match self {
+            RngMode::Seeded => 0,
+            RngMode::Vip => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<RngMode> {
+        match id {
+            0 => Some(RngMode::Seeded),
+            1 => Some(RngMode::Vip),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RngMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            RngMode::Seeded => "seeded",
+            RngMode::Vip => "vip",
+        })
+    }
+}
+
+impl FromStr for RngMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RngMode, String> {
+        match s {
+            "seeded" => Ok(RngMode::Seeded),
+            "vip" => Ok(RngMode::Vip),
+            _ => Err(format!("unknown rng `{}` (expected seeded or vip)", s)),
+        }
+    }
+}
+
+// SplitMix64, any seed (including 0) gives a full-period sequence
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl RandomSource for SplitMix64 {
+    fn next_byte(&mut self) -> u8 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        ((z ^ (z >> 31)) >> 56) as u8
+    }
+
+    fn save(&self) -> (RngMode, u64) {
+        (RngMode::Seeded, self.state)
+    }
+}
+
+// page 0x01xx of the COSMAC VIP's CHIP-8 interpreter, which the CXKK routine
+// reads as its source of noise. 0x100-0x104 and 0x1F2-0x1FF aren't code
+const VIP_PAGE: [u8; 256] = [
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x45, 0xA3, 0x98, 0x56, 0xD4, 0xF8, 0x81, 0xBC, 0xF8, 0x95, 0xAC,
+    0x22, 0xDC, 0x12, 0x56, 0xD4, 0x06, 0xB8, 0xD4, 0x06, 0xA8, 0xD4, 0x64, 0x0A, 0x01, 0xE6, 0x8A,
+    0xF4, 0xAA, 0x3B, 0x28, 0x9A, 0xFC, 0x01, 0xBA, 0xD4, 0xF8, 0x81, 0xBA, 0x06, 0xFA, 0x0F, 0xAA,
+    0x0A, 0xAA, 0xD4, 0xE6, 0x06, 0xBF, 0x93, 0xBE, 0xF8, 0x1B, 0xAE, 0x2A, 0x1A, 0xF8, 0x00, 0x5A,
+    0x0E, 0xF5, 0x3B, 0x4B, 0x56, 0x0A, 0xFC, 0x01, 0x5A, 0x30, 0x40, 0x4E, 0xF6, 0x3B, 0x3C, 0x9F,
+    0x56, 0x2A, 0x2A, 0xD4, 0x00, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x07, 0x5A, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x5B, 0x12, 0xD4, 0x22, 0x86, 0x52, 0xF8, 0xF0, 0xA7, 0x0A, 0x57, 0x87, 0xF3, 0x17,
+    0x1A, 0x3A, 0x6B, 0x12, 0xD4, 0x15, 0x85, 0x22, 0x73, 0x95, 0x52, 0x25, 0x45, 0xA5, 0x86, 0xFA,
+    0x0F, 0xB5, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x82, 0x15, 0x15, 0xD4, 0x45, 0xE6, 0xF3, 0x3A, 0x88,
+    0xD4, 0x45, 0x07, 0x30, 0x8C, 0x45, 0x07, 0x30, 0x84, 0xE6, 0x62, 0x26, 0x45, 0xA3, 0x36, 0x88,
+    0xD4, 0x3E, 0x88, 0xD4, 0xF8, 0xF0, 0xA7, 0xE7, 0x45, 0xF4, 0xA5, 0x86, 0xFA, 0x0F, 0x3B, 0xB2,
+    0xFC, 0x01, 0xB5, 0xD4, 0x45, 0x56, 0xD4, 0x45, 0xE6, 0xF4, 0x56, 0xD4, 0x45, 0xFA, 0x0F, 0x3A,
+    0xC4, 0x07, 0x56, 0xD4, 0xAF, 0x22, 0xF8, 0xD3, 0x73, 0x8F, 0xF9, 0xF0, 0x52, 0xE6, 0x07, 0xD2,
+    0x56, 0xF8, 0xFF, 0xA6, 0xF8, 0x00, 0x7E, 0x56, 0xD4, 0x19, 0x89, 0xAE, 0x93, 0xBE, 0x99, 0xEE,
+    0xF4, 0x56, 0x76, 0xE6, 0xF4, 0xB9, 0x56, 0x45, 0xF2, 0x56, 0xD4, 0x45, 0xAA, 0x86, 0xFA, 0x0F,
+    0xBA, 0xD4, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// the VIP interpreter's CXKK routine at 0x1D9. R9 is the seed: bump it, add
+// the interpreter byte at 0x01 R9.0 to R9.1, rotate that right through the
+// carry and add it again; the sum becomes R9.1 and the random byte
+pub struct VipRandom {
+    r9: u16,
+}
+
+impl RandomSource for VipRandom {
+    fn next_byte(&mut self) -> u8 {
+        self.r9 = self.r9.wrapping_add(1);
+        let [high, low] = self.r9.to_be_bytes();
+        let (sum, carry) = VIP_PAGE[low as usize].overflowing_add(high);
+        let value = (sum >> 1 | (carry as u8) << 7).wrapping_add(sum);
+        self.r9 = u16::from_be_bytes([value, low]);
+        value
+    }
+
+    fn save(&self) -> (RngMode, u64) {
+        (RngMode::Vip, self.r9 as u64)
+    }
+}
+
+#[cfg(test)]
+#[path = "./rng_tests.rs"]
+mod rng_tests;
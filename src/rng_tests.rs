@@ -0,0 +1,59 @@
+use super::*;
+
+fn bytes(rng: &mut dyn RandomSource, count: usize) -> Vec<u8> {
+    (0..count).map(|_| rng.next_byte()).collect()
+}
+
+#[test]
+fn test_seeded_is_reproducible() {
+    let first = bytes(RngMode::Seeded.create(42).as_mut(), 32);
+    let second = bytes(RngMode::Seeded.create(42).as_mut(), 32);
+    let other = bytes(RngMode::Seeded.create(43).as_mut(), 32);
+
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+#[test]
+fn test_save_restores_sequence() {
+    for mode in [RngMode::Seeded, RngMode::Vip] {
+        let mut rng = mode.create(7);
+        bytes(rng.as_mut(), 5);
+
+        let (saved_mode, state) = rng.save();
+        assert_eq!(saved_mode, mode);
+        let mut restored = saved_mode.create(state);
+        assert_eq!(bytes(restored.as_mut(), 16), bytes(rng.as_mut(), 16));
+    }
+}
+
+#[test]
+fn test_vip_sequence() {
+    // R9 = 0x00D8 steps onto the CXKK routine's own bytes: 0x19 + 0 = 0x19,
+    // 0x0C + 0x19 = 0x25; 0x89 + 0x25 = 0xAE, 0x57 + 0xAE = 0x05; and so on
+    let mut rng = RngMode::Vip.create(0x00D8);
+    assert_eq!(
+        bytes(rng.as_mut(), 8),
+        [0x25, 0x05, 0x0C, 0xEE, 0x82, 0xA8, 0x61, 0xFF]
+    );
+    assert_eq!(rng.save(), (RngMode::Vip, 0xFFE0));
+
+    // a carry out of the first add is rotated into bit 7: 0xD4 + 0x80 = 0x154,
+    // 0xAA + 0x54 = 0xFE
+    let mut rng = RngMode::Vip.create(0x8008);
+    assert_eq!(bytes(rng.as_mut(), 1), [0xFE]);
+
+    // INC R9 carries into the high byte: 0x00 + 1 = 1, 0 + 1 = 1
+    let mut rng = RngMode::Vip.create(0x00FF);
+    assert_eq!(bytes(rng.as_mut(), 1), [0x01]);
+    assert_eq!(rng.save(), (RngMode::Vip, 0x0100));
+}
+
+#[test]
+fn test_parse_mode() {
+    assert_eq!("vip".parse(), Ok(RngMode::Vip));
+    assert_eq!(RngMode::Seeded.to_string(), "seeded");
+    assert_eq!(RngMode::from_id(RngMode::Vip.id()), Some(RngMode::Vip));
+    assert_eq!(RngMode::from_id(2), None);
+    assert!("xorshift".parse::<RngMode>().is_err());
+}
@@ -0,0 +1,109 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+
+use crate::{config::Config, quirks::Profile, unhex, RomHash};
+
+// the database compiled into the binary, see the file for the format
+const BUILTIN: &str = include_str!("./romdb.toml");
+
+// what the database knows about one ROM, anything left out falls back to the
+// command line defaults
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RomSettings {
+    pub title: Option<String>,
+    // also decides the platform
+    pub profile: Option<Profile>,
+    pub ips: Option<u32>,
+    // the palette and key bindings, layered in with the config files
+    pub config: Config,
+}
+
+// the settings of a table that aren't config settings, before they're checked
+#[derive(Deserialize)]
+struct Entry {
+    title: Option<String>,
+    profile: Option<String>,
+    ips: Option<u32>,
+}
+
+// ROM settings by the SHA-1 of the ROM image
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RomDb {
+    roms: HashMap<RomHash, RomSettings>,
+}
+
+impl RomDb {
+    pub fn builtin() -> RomDb {
+        // checked by the tests
+        BUILTIN.parse().expect("built-in ROM database")
+    }
+
+    // entries in `other` replace the ones here for the same ROM
+    pub fn extend(&mut self, other: RomDb) {
+        self.roms.extend(other.roms);
+    }
+
+    pub fn get(&self, hash: &RomHash) -> Option<&RomSettings> {
+        self.roms.get(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.roms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.roms.is_empty()
+    }
+}
+
+impl FromStr for RomDb {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<RomDb, String> {
+        // sorted, so the first bad entry in the file is the one reported
+        let tables: BTreeMap<String, toml::Table> =
+            toml::from_str(s).map_err(|err| err.message().to_string())?;
+        let mut roms = HashMap::new();
+        for (sha1, mut table) in tables {
+            let hash = unhex(&sha1)?;
+            let invalid = |err: toml::de::Error| format!("{}: {}", sha1, err.message());
+            // split off, so the rest goes through Config and its unknown field check
+            let entry: toml::Table = ["title", "profile", "ips"]
+                .into_iter()
+                .filter_map(|name| Some((name.to_string(), table.remove(name)?)))
+                .collect();
+            let entry: Entry = entry.try_into().map_err(invalid)?;
+            let config: Config = table.try_into().map_err(invalid)?;
+
+            let profile = entry
+                .profile
+                .map(|profile| profile.parse())
+                .transpose()
+                .map_err(|err| format!("{}.profile: {}", sha1, err))?;
+            if entry.ips == Some(0) {
+                return Err(format!("{}.ips: has to be at least 1", sha1));
+            }
+            config
+                .validate()
+                .map_err(|err| format!("{}.{}", sha1, err))?;
+            let settings = RomSettings {
+                title: entry.title,
+                profile,
+                ips: entry.ips,
+                config,
+            };
+            if roms.insert(hash, settings).is_some() {
+                return Err(format!("{}: listed twice", sha1));
+            }
+        }
+        Ok(RomDb { roms })
+    }
+}
+
+#[cfg(test)]
+#[path = "./romdb_tests.rs"]
+mod romdb_tests;
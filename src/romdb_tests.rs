@@ -0,0 +1,109 @@
+use super::*;
+
+const HASH: &str = "0123456789abcdef0123456789abcdef01234567";
+
+fn hash() -> RomHash {
+    crate::unhex(HASH).unwrap()
+}
+
+#[test]
+fn test_builtin() {
+    // parsed at startup, so a bad entry would stop every ROM from running
+    RomDb::builtin();
+}
+
+#[test]
+fn test_lookup_by_rom() {
+    let rom = [0x00, 0xE0, 0x12, 0x00];
+    let db: RomDb = format!(
+        "[\"{}\"]\nprofile = \"xochip\"",
+        crate::hex(&crate::hash_rom(&rom))
+    )
+    .parse()
+    .unwrap();
+
+    let settings = db.get(&crate::hash_rom(&rom)).unwrap();
+    assert_eq!(settings.profile, Some(Profile::XoChip));
+    assert_eq!(db.get(&crate::hash_rom(&rom[..2])), None);
+}
+
+#[test]
+fn test_parse() {
+    let db: RomDb = format!(
+        "
+        [\"{}\"]
+        title = \"Some Game\"
+        profile = \"schip\"
+        ips = 1500
+        palette = \"amber\"
+        replace-keys = true
+
+        [\"{}\".keys]
+        Space = 0x5
+        ",
+        HASH, HASH
+    )
+    .parse()
+    .unwrap();
+
+    let settings = db.get(&hash()).unwrap();
+    assert_eq!(settings.title.as_deref(), Some("Some Game"));
+    assert_eq!(settings.profile, Some(Profile::SuperChipModern));
+    assert_eq!(settings.ips, Some(1500));
+    assert_eq!(settings.config.palette.as_deref(), Some("amber"));
+    assert!(settings.config.replace_keys);
+    assert_eq!(settings.config.keys["Space"].keys(), [5]);
+    assert_eq!(db.get(&[0; 20]), None);
+}
+
+#[test]
+fn test_extend() {
+    let mut db: RomDb = format!("[\"{}\"]\ntitle = \"old\"\nips = 500", HASH)
+        .parse()
+        .unwrap();
+    db.extend(format!("[\"{}\"]\ntitle = \"new\"", HASH).parse().unwrap());
+
+    // the whole entry is replaced, not merged
+    assert_eq!(db.len(), 1);
+    let settings = db.get(&hash()).unwrap();
+    assert_eq!(settings.title.as_deref(), Some("new"));
+    assert_eq!(settings.ips, None);
+}
+
+#[test]
+fn test_errors() {
+    let errors = [
+        ("[abc]", "invalid hash `abc`".to_string()),
+        (
+            "[\"HASH\"]\nprofile = \"cosmac\"",
+            format!(
+                "{}.profile: unknown profile `cosmac` (expected vip, chip48, schip, schip-legacy or xochip)",
+                HASH
+            ),
+        ),
+        ("[\"HASH\"]\nips = 0", format!("{}.ips: has to be at least 1", HASH)),
+        (
+            "[\"HASH\"]\nkeys = { Up = 0x10 }",
+            format!("{}.keys.Up: 0x10 is not a keypad key", HASH),
+        ),
+    ];
+    for (text, expected) in errors {
+        assert_eq!(
+            text.replace("HASH", HASH).parse::<RomDb>(),
+            Err(expected),
+            "{}",
+            text
+        );
+    }
+
+    let twice = format!("[\"{}\"]\n[\"{}\"]", HASH, HASH.to_uppercase());
+    assert!(twice
+        .parse::<RomDb>()
+        .unwrap_err()
+        .ends_with("listed twice"));
+    // anything that isn't a ROM or config setting is a typo
+    let unknown = format!("[\"{}\"]\nspeed = 3", HASH).parse::<RomDb>();
+    assert!(unknown
+        .unwrap_err()
+        .starts_with(&format!("{}: unknown field `speed`", HASH)));
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+
+use crate::{
+    cpu::{Cpu, KeyWait, Platform},
+    display::{HEIGHT, WIDTH},
+    rng::RngMode,
+    Machine, RomHash,
+};
+
+const MAGIC: &[u8; 4] = b"MRSS";
+// bump whenever the layout below changes once it's been released
+pub const VERSION: u16 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    NotASaveState,
+    UnsupportedVersion(u16),
+    RomMismatch,
+    Truncated,
+    Corrupt(&'static str),
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateError::NotASaveState => write!(f, "not a save state"),
+            StateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {}", version)
+            }
+            StateError::RomMismatch => write!(f, "save state was made with a different ROM"),
+            StateError::Truncated => write!(f, "save state is truncated"),
+            StateError::Corrupt(what) => write!(f, "save state has an invalid {}", what),
+        }
+    }
+}
+
+impl std::error::Error for StateError {}
+
+// layout, multi-byte values are little endian:
+// magic, version, rom hash, platform, pc, i, v, sp, stack, dt, st, rpl,
+// audio pattern, pitch, exited, rng mode, rng state, hires, planes, framebuffer, keys, memory length, memory,
+// key wait: 0, 1 then the register and held keys, or 2 then the register and key,
+// vblank wait
+pub fn save(machine: &Machine) -> Vec<u8> {
+    let cpu = &machine.cpu;
+    let mut out = Vec::with_capacity(64 + WIDTH * HEIGHT + cpu.memory.len());
+
+    out.extend(MAGIC);
+    out.extend(VERSION.to_le_bytes());
+    out.extend(machine.rom_hash());
+
+    out.push(match cpu.platform {
+        Platform::Chip8 => 0,
+        Platform::SuperChip => 1,
+        Platform::XoChip => 2,
+    });
+    out.extend(cpu.pc.to_le_bytes());
+    out.extend(cpu.i.to_le_bytes());
+    out.extend(cpu.v);
+    out.push(cpu.sp);
+    for addr in cpu.stack {
+        out.extend(addr.to_le_bytes());
+    }
+    out.push(cpu.dt);
+    out.push(cpu.st);
+    out.extend(cpu.rpl);
+    out.extend(cpu.audio_pattern);
+    out.push(cpu.pitch);
+    out.push(cpu.exited as u8);
+    let (rng_mode, rng_state) = cpu.rng.save();
+    out.push(rng_mode.id());
+    out.extend(rng_state.to_le_bytes());
+
+    out.push(cpu.display.hires as u8);
+    out.push(cpu.display.planes);
+    for row in &cpu.display.memory {
+        out.extend(row);
+    }
+    out.extend(cpu.keypad.keys.map(|key| key as u8));
+
+    out.extend((cpu.memory.len() as u32).to_le_bytes());
+    out.extend(&cpu.memory);
+
+    match cpu.key_wait {
+        None => out.push(0),
+        Some(KeyWait::Press { register, held }) => {
+            out.extend([1, register as u8]);
+            out.extend(held.map(|key| key as u8));
+        }
+        Some(KeyWait::Release { register, key }) => out.extend([2, register as u8, key]),
+    }
+    // only set mid-frame, but the debugger can pause there
+    out.push(cpu.vblank_wait as u8);
+    out
+}
+
+pub fn load(machine: &mut Machine, data: &[u8]) -> Result<(), StateError> {
+    let mut reader = Reader { data };
+
+    if reader.bytes(MAGIC.len()).ok() != Some(MAGIC.as_slice()) {
+        return Err(StateError::NotASaveState);
+    }
+    let version = u16::from_le_bytes(reader.array()?);
+    if version != VERSION {
+        return Err(StateError::UnsupportedVersion(version));
+    }
+    let rom_hash: RomHash = reader.array()?;
+    if rom_hash != machine.rom_hash() {
+        return Err(StateError::RomMismatch);
+    }
+
+    // everything is read into a fresh cpu first, so a bad state can't leave the machine half loaded
+    let mut cpu = Cpu::new(machine.cpu.quirks);
+    cpu.set_platform(match reader.byte()? {
+        0 => Platform::Chip8,
+        1 => Platform::SuperChip,
+        2 => Platform::XoChip,
+        _ => return Err(StateError::Corrupt("platform")),
+    });
+    cpu.pc = u16::from_le_bytes(reader.array()?);
+    cpu.i = u16::from_le_bytes(reader.array()?);
+    cpu.v = reader.array()?;
+    cpu.sp = reader.byte()?;
+    if cpu.sp as usize > cpu.stack.len() {
+        return Err(StateError::Corrupt("stack pointer"));
+    }
+    for addr in cpu.stack.iter_mut() {
+        *addr = u16::from_le_bytes(reader.array()?);
+    }
+    cpu.dt = reader.byte()?;
+    cpu.st = reader.byte()?;
+    cpu.rpl = reader.array()?;
+    cpu.audio_pattern = reader.array()?;
+    cpu.pitch = reader.byte()?;
+    cpu.exited = reader.bool()?;
+    let rng_mode = RngMode::from_id(reader.byte()?).ok_or(StateError::Corrupt("rng mode"))?;
+    cpu.rng = rng_mode.create(u64::from_le_bytes(reader.array()?));
+
+    cpu.display.hires = reader.bool()?;
+    cpu.display.planes = reader.byte()?;
+    for row in cpu.display.memory.iter_mut() {
+        *row = reader.array()?;
+    }
+    for key in cpu.keypad.keys.iter_mut() {
+        *key = reader.bool()?;
+    }
+
+    let memory_len = u32::from_le_bytes(reader.array()?) as usize;
+    if memory_len != cpu.memory.len() {
+        return Err(StateError::Corrupt("memory size"));
+    }
+    cpu.memory.copy_from_slice(reader.bytes(memory_len)?);
+
+    cpu.key_wait = match reader.byte()? {
+        0 => None,
+        1 => {
+            let register = reader.nibble("key wait")?;
+            let mut held = [false; 16];
+            for key in held.iter_mut() {
+                *key = reader.bool()?;
+            }
+            Some(KeyWait::Press { register, held })
+        }
+        2 => Some(KeyWait::Release {
+            register: reader.nibble("key wait")?,
+            key: reader.nibble("key wait")? as u8,
+        }),
+        _ => return Err(StateError::Corrupt("key wait")),
+    };
+    cpu.vblank_wait = reader.bool()?;
+    if !reader.data.is_empty() {
+        return Err(StateError::Corrupt("length"));
+    }
+
+    cpu.display.draw_flag = true;
+    cpu.memory_trace = machine.cpu.memory_trace.take();
+    machine.cpu = cpu;
+    Ok(())
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], StateError> {
+        if self.data.len() < len {
+            return Err(StateError::Truncated);
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], StateError> {
+        Ok(self.bytes(N)?.try_into().unwrap())
+    }
+
+    fn byte(&mut self) -> Result<u8, StateError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    // a register or key number
+    fn nibble(&mut self, what: &'static str) -> Result<usize, StateError> {
+        match self.byte()? {
+            byte @ 0..=0xF => Ok(byte as usize),
+            _ => Err(StateError::Corrupt(what)),
+        }
+    }
+
+    fn bool(&mut self) -> Result<bool, StateError> {
+        match self.byte()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(StateError::Corrupt("flag")),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./savestate_tests.rs"]
+mod savestate_tests;
@@ -0,0 +1,137 @@
+use super::*;
+use crate::{quirks::Quirks, rng::RngMode};
+
+// V0 = 5, draw the font's 0, set the delay timer, call 0x20C, spin
+const ROM: [u8; 14] = [
+    0x60, 0x05, 0xD1, 0x15, 0xF0, 0x15, 0x22, 0x0C, 0x12, 0x08, 0x00, 0x00, 0x12, 0x0C,
+];
+
+fn build_machine(platform: Platform) -> Machine {
+    let mut machine = Machine::new(platform, Quirks::default());
+    machine.load_rom(&ROM);
+    machine
+}
+
+#[test]
+fn test_round_trip() {
+    let mut machine = build_machine(Platform::Chip8);
+    // the draw waits for vblank, so the call happens in the second frame
+    machine.run_frame().unwrap();
+    machine.run_frame().unwrap();
+    machine.set_key(0xA, true);
+    machine.cpu.rpl[3] = 9;
+    let state = machine.save_state();
+
+    let mut restored = build_machine(Platform::Chip8);
+    restored.load_state(&state).unwrap();
+
+    assert_eq!(restored.cpu.pc, machine.cpu.pc);
+    assert_eq!(restored.cpu.i, machine.cpu.i);
+    assert_eq!(restored.cpu.v, machine.cpu.v);
+    assert_eq!(restored.cpu.sp, 1);
+    assert_eq!(restored.cpu.stack, machine.cpu.stack);
+    assert_eq!(restored.cpu.dt, machine.cpu.dt);
+    assert_eq!(restored.cpu.rpl[3], 9);
+    assert_eq!(restored.cpu.memory, machine.cpu.memory);
+    assert_eq!(restored.cpu.display.memory, machine.cpu.display.memory);
+    assert!(restored.cpu.keypad.keys[0xA]);
+    assert!(restored.cpu.display.draw_flag);
+    assert_eq!(restored.save_state(), state);
+}
+
+#[test]
+fn test_key_wait_is_restored() {
+    let mut machine = build_machine(Platform::Chip8);
+    machine.cpu.key_wait = Some(KeyWait::Press {
+        register: 4,
+        held: [true; 16],
+    });
+    let state = machine.save_state();
+
+    let mut restored = build_machine(Platform::Chip8);
+    restored.load_state(&state).unwrap();
+    assert_eq!(restored.cpu.key_wait, machine.cpu.key_wait);
+
+    let mut corrupt = state.clone();
+    corrupt[state.len() - 18] = 0x10;
+    assert_eq!(
+        restored.load_state(&corrupt),
+        Err(StateError::Corrupt("key wait"))
+    );
+}
+
+#[test]
+fn test_vblank_wait_is_restored() {
+    let mut machine = build_machine(Platform::Chip8);
+    machine.cpu.vblank_wait = true;
+    let state = machine.save_state();
+
+    let mut restored = build_machine(Platform::Chip8);
+    restored.load_state(&state).unwrap();
+    assert!(restored.cpu.vblank_wait);
+    assert!(!restored.frame_has_time());
+}
+
+#[test]
+fn test_platform_is_restored() {
+    let machine = build_machine(Platform::XoChip);
+    let state = machine.save_state();
+
+    let mut restored = build_machine(Platform::Chip8);
+    restored.load_state(&state).unwrap();
+
+    assert_eq!(restored.cpu.platform, Platform::XoChip);
+    assert_eq!(restored.cpu.memory.len(), 0x10000);
+}
+
+#[test]
+fn test_rom_mismatch() {
+    let state = build_machine(Platform::Chip8).save_state();
+    let mut other = Machine::new(Platform::Chip8, Quirks::default());
+    other.load_rom(&[0x12, 0x00]);
+
+    assert_eq!(other.load_state(&state), Err(StateError::RomMismatch));
+}
+
+#[test]
+fn test_invalid_states() {
+    let mut machine = build_machine(Platform::Chip8);
+    machine.step().unwrap();
+    let state = machine.save_state();
+
+    assert_eq!(
+        machine.load_state(b"not a state"),
+        Err(StateError::NotASaveState)
+    );
+    assert_eq!(
+        machine.load_state(&state[..state.len() - 1]),
+        Err(StateError::Truncated)
+    );
+
+    let mut future = state.clone();
+    future[4] = 99;
+    assert_eq!(
+        machine.load_state(&future),
+        Err(StateError::UnsupportedVersion(99))
+    );
+
+    // a failed load leaves the machine alone
+    assert_eq!(machine.cpu.pc, 0x202);
+    assert_eq!(machine.cpu.v[0], 5);
+}
+
+#[test]
+fn test_rng_is_restored() {
+    let mut machine = build_machine(Platform::Chip8);
+    machine.cpu.rng = RngMode::Vip.create(0x1234);
+    machine.cpu.rng.next_byte();
+    let state = machine.save_state();
+    let expected = machine.cpu.rng.next_byte();
+
+    let mut restored = build_machine(Platform::Chip8);
+    restored.cpu.rng = RngMode::Seeded.create(5);
+    restored.load_state(&state).unwrap();
+
+    assert_eq!(restored.cpu.rng.save().0, RngMode::Vip);
+    assert_eq!(restored.cpu.rng.next_byte(), expected);
+}
@@ -0,0 +1,64 @@
+use std::time::{Duration, Instant};
+
+use crate::TIMER_FREQUENCY;
+
+// the most frames run at once to make up for a stall; anything longer is
+// dropped rather than fast-forwarding through it
+pub const MAX_CATCH_UP: u32 = 10;
+
+// paces emulated frames at exactly TIMER_FREQUENCY off a monotonic clock, so
+// it doesn't matter how often the frontend polls it
+pub struct Scheduler {
+    start: Instant,
+    // frames handed out since `start`
+    frames: u64,
+}
+
+impl Scheduler {
+    pub fn new(now: Instant) -> Scheduler {
+        Scheduler {
+            start: now,
+            frames: 0,
+        }
+    }
+
+    // when frame `frame` is due, computed from the start so rounding never adds up
+    fn deadline(&self, frame: u64) -> Instant {
+        self.start + Duration::from_nanos(frame * 1_000_000_000 / TIMER_FREQUENCY as u64)
+    }
+
+    // how many frames to run now, counting any missed while the frontend was busy
+    pub fn frames_due(&mut self, now: Instant) -> u32 {
+        let elapsed = now.saturating_duration_since(self.start).as_nanos();
+        let due = (elapsed * TIMER_FREQUENCY as u128 / 1_000_000_000) as u64 + 1;
+        let mut behind = due.saturating_sub(self.frames);
+        if behind > MAX_CATCH_UP as u64 {
+            // skips the lost time, so the frames after the stall stay evenly spaced
+            self.frames = due - MAX_CATCH_UP as u64;
+            behind = MAX_CATCH_UP as u64;
+        }
+        self.frames += behind;
+        behind as u32
+    }
+
+    // when the next frame will be due
+    pub fn next_frame(&self) -> Instant {
+        self.deadline(self.frames)
+    }
+}
+
+// how long to sleep after presenting a frame. with vsync `present` blocks
+// until the display's next refresh, so frames go out at the monitor's rate;
+// if it came back well within one refresh the driver isn't syncing, and the
+// rest of the refresh is slept off instead of spinning
+pub fn present_wait(since_last_present: Duration, refresh: Duration) -> Duration {
+    if since_last_present < refresh / 2 {
+        refresh - since_last_present
+    } else {
+        Duration::ZERO
+    }
+}
+
+#[cfg(test)]
+#[path = "./scheduler_tests.rs"]
+mod scheduler_tests;
@@ -0,0 +1,60 @@
+use super::*;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn test_present_wait() {
+    let refresh = Duration::from_micros(6944);
+    // vsync already waited for the refresh
+    assert_eq!(present_wait(refresh, refresh), Duration::ZERO);
+    assert_eq!(present_wait(ms(5), refresh), Duration::ZERO);
+    assert_eq!(present_wait(ms(20), refresh), Duration::ZERO);
+    // presenting returned at once, sleep out the rest of the refresh
+    assert_eq!(present_wait(ms(1), refresh), Duration::from_micros(5944));
+    assert_eq!(present_wait(Duration::ZERO, refresh), refresh);
+}
+
+#[test]
+fn test_paced_at_60hz() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(start);
+    // the first frame runs straight away
+    assert_eq!(scheduler.frames_due(start), 1);
+    assert_eq!(scheduler.frames_due(start + ms(10)), 0);
+    assert_eq!(scheduler.frames_due(start + ms(17)), 1);
+    // polling faster than the frame rate, e.g. at a 144 Hz refresh, doesn't run extra frames
+    let mut frames = 2;
+    for tick in 1..=144 {
+        frames +=
+            scheduler.frames_due(start + ms(17) + Duration::from_nanos(tick * 1_000_000_000 / 144));
+    }
+    assert_eq!(frames, 62);
+}
+
+#[test]
+fn test_no_drift() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(start);
+    // polled every 16 ms, which used to give 62.5 frames a second
+    let mut frames = 0;
+    for tick in 0..625 {
+        frames += scheduler.frames_due(start + ms(16 * tick));
+    }
+    assert_eq!(frames, 600);
+    assert_eq!(scheduler.next_frame(), start + ms(10_000));
+}
+
+#[test]
+fn test_catch_up() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(start);
+    scheduler.frames_due(start);
+    // a short stall is made up for
+    assert_eq!(scheduler.frames_due(start + ms(100)), 6);
+    // a long one only runs the most recent frames, then carries on evenly
+    assert_eq!(scheduler.frames_due(start + ms(2_000)), MAX_CATCH_UP);
+    assert_eq!(scheduler.frames_due(start + ms(2_010)), 0);
+    assert_eq!(scheduler.frames_due(start + ms(2_017)), 1);
+}
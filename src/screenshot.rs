@@ -0,0 +1,127 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use crate::{display::Display, persistence::Shades};
+
+// grey levels for the bitplanes set in a framebuffer cell, matching the window's colors
+pub const GREYS: [u8; 4] = [0, 255, 170, 85];
+
+// ASCII art characters, indexed the same way
+const ASCII: [char; 4] = ['.', '#', '+', '*'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    // plain (P1) portable bitmap, any lit plane is black
+    Pbm,
+    Ascii,
+}
+
+impl ImageFormat {
+    // picks the format from the extension: .png, .pbm, or .txt for ASCII art
+    pub fn from_path(path: &Path) -> Result<ImageFormat, String> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("");
+        extension.to_ascii_lowercase().parse()
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Pbm => "pbm",
+            ImageFormat::Ascii => "txt",
+        })
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ImageFormat, String> {
+        match s {
+            "png" => Ok(ImageFormat::Png),
+            "pbm" => Ok(ImageFormat::Pbm),
+            "txt" | "ascii" => Ok(ImageFormat::Ascii),
+            _ => Err(format!(
+                "unknown image format `{}` (expected png, pbm or txt)",
+                s
+            )),
+        }
+    }
+}
+
+// the visible part of the framebuffer, one image pixel per CHIP-8 pixel
+pub fn encode(display: &Display, format: ImageFormat) -> Vec<u8> {
+    encode_shaded(&Shades::from(display), format)
+}
+
+// the same from smoothed levels: PNGs blend the greys, the other formats only
+// have whole pixels so planes at least half lit count as set
+pub fn encode_shaded(shades: &Shades, format: ImageFormat) -> Vec<u8> {
+    match format {
+        ImageFormat::Png => png(shades),
+        ImageFormat::Pbm => pbm(shades).into_bytes(),
+        ImageFormat::Ascii => ascii_shaded(shades).into_bytes(),
+    }
+}
+
+fn rows(shades: &Shades) -> impl Iterator<Item = Vec<u8>> + '_ {
+    (0..shades.height()).map(|y| (0..shades.width()).map(|x| shades.cell(x, y)).collect())
+}
+
+fn png(shades: &Shades) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, shades.width() as u32, shades.height() as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut pixels = Vec::with_capacity(shades.width() * shades.height());
+    for y in 0..shades.height() {
+        for x in 0..shades.width() {
+            let grey: f32 = GREYS
+                .iter()
+                .zip(shades.weights(x, y))
+                .map(|(&grey, weight)| grey as f32 * weight)
+                .sum();
+            pixels.push(grey.round() as u8);
+        }
+    }
+    // writing into a Vec can't fail
+    let mut writer = encoder.write_header().expect("PNG header");
+    writer.write_image_data(&pixels).expect("PNG data");
+    writer.finish().expect("PNG end");
+    out
+}
+
+fn pbm(shades: &Shades) -> String {
+    let mut out = format!("P1\n{} {}\n", shades.width(), shades.height());
+    for row in rows(shades) {
+        let bits: Vec<&str> = row
+            .iter()
+            .map(|&cell| if cell != 0 { "1" } else { "0" })
+            .collect();
+        out.push_str(&bits.join(" "));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn ascii(display: &Display) -> String {
+    ascii_shaded(&Shades::from(display))
+}
+
+fn ascii_shaded(shades: &Shades) -> String {
+    let mut out = String::new();
+    for row in rows(shades) {
+        out.extend(row.iter().map(|&cell| ASCII[cell as usize]));
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+#[path = "./screenshot_tests.rs"]
+mod screenshot_tests;
@@ -0,0 +1,85 @@
+use super::*;
+
+fn build_display() -> Display {
+    let mut display = Display::new();
+    display.set_pixel(0, 0, 1, true);
+    display.set_pixel(1, 0, 2, true);
+    display.set_pixel(2, 0, 3, true);
+    // outside the lo-res screen, only shows up in hi-res
+    display.set_pixel(100, 40, 1, true);
+    display
+}
+
+#[test]
+fn test_format_from_path() {
+    assert_eq!(
+        ImageFormat::from_path(Path::new("out.png")),
+        Ok(ImageFormat::Png)
+    );
+    assert_eq!(
+        ImageFormat::from_path(Path::new("out.PBM")),
+        Ok(ImageFormat::Pbm)
+    );
+    assert_eq!(
+        ImageFormat::from_path(Path::new("a/out.txt")),
+        Ok(ImageFormat::Ascii)
+    );
+    assert!(ImageFormat::from_path(Path::new("out.jpg")).is_err());
+    assert!(ImageFormat::from_path(Path::new("out")).is_err());
+}
+
+#[test]
+fn test_ascii() {
+    let text = ascii(&build_display());
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(lines.len(), 32);
+    assert!(lines.iter().all(|line| line.len() == 64));
+    assert!(lines[0].starts_with("#+*..."));
+    assert!(lines[1].chars().all(|c| c == '.'));
+}
+
+#[test]
+fn test_pbm() {
+    let mut display = build_display();
+    display.hires = true;
+    let text = String::from_utf8(encode(&display, ImageFormat::Pbm)).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+
+    assert_eq!(&lines[..2], ["P1", "128 64"]);
+    assert!(lines[2].starts_with("1 1 1 0 "));
+    assert_eq!(lines[2 + 40].split(' ').nth(100), Some("1"));
+    assert_eq!(lines.len(), 2 + 64);
+}
+
+#[test]
+fn test_png() {
+    let data = encode(&build_display(), ImageFormat::Png);
+
+    let decoder = png::Decoder::new(data.as_slice());
+    let mut reader = decoder.read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels).unwrap();
+
+    assert_eq!((info.width, info.height), (64, 32));
+    assert_eq!(info.color_type, png::ColorType::Grayscale);
+    assert_eq!(&pixels[..4], [255, 170, 85, 0]);
+}
+
+#[test]
+fn test_shaded() {
+    let mut shades = Shades::from(&build_display());
+    // a half faded plane 1 pixel and a dimmer plane 2 one
+    shades.levels[0][0] = [0.5, 0.0];
+    shades.levels[0][1] = [0.0, 0.4];
+
+    let data = encode_shaded(&shades, ImageFormat::Png);
+    let mut reader = png::Decoder::new(data.as_slice()).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    reader.next_frame(&mut pixels).unwrap();
+    assert_eq!(&pixels[..3], [128, 68, 85]);
+
+    // whole-pixel formats round to the nearest cell
+    let text = String::from_utf8(encode_shaded(&shades, ImageFormat::Ascii)).unwrap();
+    assert!(text.starts_with("#.*"));
+}
@@ -0,0 +1,139 @@
+// runs every ROM in tests/roms/ and compares the screen it ends on with the
+// `.golden` file next to it, or with each of `<name>.<label>.golden` for a ROM
+// that's checked under several profiles. set MARISA_BLESS=1 to write missing
+// goldens and update the ones that don't match, then review the changes
+// before committing
+use std::path::{Path, PathBuf};
+
+use marisa_rs::{assembler, golden::Golden, quirks::Profile};
+
+const ROMS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/roms");
+// frames run for a ROM that doesn't have a golden yet
+const DEFAULT_FRAMES: u64 = 120;
+
+fn read_rom(path: &Path) -> Result<Vec<u8>, String> {
+    if path.extension().is_some_and(|extension| extension == "8o") {
+        let source = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        return assembler::assemble(&source)
+            .map(|program| program.rom)
+            .map_err(|err| err.to_string());
+    }
+    std::fs::read(path).map_err(|err| err.to_string())
+}
+
+// the platform a ROM is for, going by the usual extensions
+fn default_profile(path: &Path) -> Profile {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("sc8") => Profile::SuperChipModern,
+        Some("xo8") => Profile::XoChip,
+        _ => Profile::CosmacVip,
+    }
+}
+
+fn roms() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(ROMS) else {
+        return Vec::new();
+    };
+    let mut roms: Vec<PathBuf> = entries
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            path.extension().is_some_and(|extension| {
+                ["ch8", "sc8", "xo8", "8o"].contains(&&*extension.to_string_lossy())
+            })
+        })
+        .collect();
+    roms.sort();
+    roms
+}
+
+// `game.golden` and any `game.<label>.golden`, or just the first when there are none yet
+fn golden_paths(rom_path: &Path) -> Vec<PathBuf> {
+    let stem = rom_path.file_stem().unwrap().to_string_lossy();
+    let labelled = format!("{}.", stem);
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(rom_path.parent().unwrap())
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| Some(entry.ok()?.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            let Some(name) = name.strip_suffix(".golden") else {
+                return false;
+            };
+            name == stem
+                || name
+                    .strip_prefix(&labelled)
+                    .is_some_and(|label| !label.contains('.'))
+        })
+        .collect();
+    if paths.is_empty() {
+        paths.push(rom_path.with_extension("golden"));
+    }
+    paths.sort();
+    paths
+}
+
+// None when the ROM matches every golden, what went wrong otherwise
+fn check(rom_path: &Path, bless: bool) -> Option<String> {
+    let rom = match read_rom(rom_path) {
+        Ok(rom) => rom,
+        Err(err) => return Some(err),
+    };
+    let problems: Vec<String> = golden_paths(rom_path)
+        .iter()
+        .filter_map(|golden_path| {
+            let problem = check_golden(rom_path, &rom, golden_path, bless)?;
+            Some(format!("{}: {}", golden_path.display(), problem))
+        })
+        .collect();
+    (!problems.is_empty()).then(|| problems.join("\n"))
+}
+
+fn check_golden(rom_path: &Path, rom: &[u8], golden_path: &Path, bless: bool) -> Option<String> {
+    let golden = match std::fs::read_to_string(golden_path) {
+        Ok(text) => match text.parse::<Golden>() {
+            Ok(golden) => golden,
+            Err(err) => return Some(err),
+        },
+        Err(_) if bless => Golden {
+            frames: DEFAULT_FRAMES,
+            profile: default_profile(rom_path),
+            keys: Vec::new(),
+            screen: String::new(),
+        },
+        Err(_) => return Some("no golden, run with MARISA_BLESS=1 to create one".to_string()),
+    };
+
+    let screen = match golden.capture(rom) {
+        Ok(screen) => screen,
+        Err(err) => return Some(err.to_string()),
+    };
+    let diff = marisa_rs::golden::diff(&golden.screen, &screen)?;
+    if bless {
+        let golden = Golden { screen, ..golden };
+        return std::fs::write(golden_path, golden.to_string())
+            .err()
+            .map(|err| err.to_string());
+    }
+    Some(diff)
+}
+
+#[test]
+fn test_golden_frames() {
+    let bless = std::env::var_os("MARISA_BLESS").is_some();
+    let mut failures = 0;
+    for rom_path in roms() {
+        let name = rom_path.file_name().unwrap().to_string_lossy();
+        match check(&rom_path, bless) {
+            None => println!("{}: ok", name),
+            Some(problem) => {
+                println!("{}: FAILED\n{}", name, problem);
+                failures += 1;
+            }
+        }
+    }
+    assert_eq!(
+        failures, 0,
+        "{} ROMs don't match their golden frames",
+        failures
+    );
+}